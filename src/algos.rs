@@ -0,0 +1,436 @@
+//! Named, pluggable solvers.
+//!
+//! Every search in [`pathfinder`](crate::pathfinder) is wrapped in a type
+//! implementing [`Solver`], and a [`Registry`] maps names to solvers. The
+//! command line looks algorithms up in [`Registry::default`], so solvers
+//! written outside this crate can be used by registering them alongside the
+//! built-in ones.
+
+pub mod errors;
+
+use std::thread;
+
+use crate::pathfinder::{self, Graph, Heuristic, NoopObserver, SearchObserver, SolveResult};
+
+use errors::ParameterError;
+
+/// A tunable setting of a [`Solver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parameter {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub default: &'static str,
+}
+
+/// A maze solving algorithm, which works on any [`Graph`].
+///
+/// Solvers must be `Send + Sync` so several can search the same graph from
+/// different threads, see [`solve_parallel`].
+pub trait Solver: Send + Sync {
+    /// Short name used to select the solver, e.g. `bfs`.
+    fn name(&self) -> &str;
+
+    /// One line summary of how the solver works.
+    fn description(&self) -> &str;
+
+    /// Settings accepted by [`set_parameter`](Solver::set_parameter).
+    fn parameters(&self) -> &[Parameter] {
+        &[]
+    }
+
+    /// Changes the setting called `name` to `value`.
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), ParameterError> {
+        let _ = value;
+        Err(ParameterError::Unknown {
+            solver: self.name().to_owned(),
+            name: name.to_owned(),
+        })
+    }
+
+    /// Whether the solver keeps to locked doors until it has their key, see
+    /// [`Maze::door_at`](crate::maze::Maze::door_at). Other solvers walk through doors as if they were
+    /// open.
+    fn opens_doors(&self) -> bool {
+        false
+    }
+
+    /// Searches `graph` for a path from its start to its exit, reporting
+    /// each step to `observer`.
+    fn solve_observed(&self, graph: &dyn Graph, observer: &mut dyn SearchObserver) -> SolveResult;
+
+    /// Searches `graph` for a path from its start to its exit.
+    fn solve(&self, graph: &dyn Graph) -> SolveResult {
+        self.solve_observed(graph, &mut NoopObserver)
+    }
+}
+
+/// Runs every solver on `graph` at the same time, one thread each, and
+/// returns their results in the same order as `solvers`.
+pub fn solve_parallel(graph: &dyn Graph, solvers: &[&dyn Solver]) -> Vec<SolveResult> {
+    thread::scope(|s| {
+        let handles: Vec<_> = solvers
+            .iter()
+            .map(|solver| s.spawn(move || solver.solve(graph)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("solver thread panicked"))
+            .collect()
+    })
+}
+
+/// Solvers looked up by name.
+pub struct Registry {
+    solvers: Vec<Box<dyn Solver>>,
+}
+
+// associate functions
+impl Registry {
+    /// An empty registry. Use [`Registry::default`] for the built-in solvers.
+    pub fn new() -> Registry {
+        Registry {
+            solvers: Vec::new(),
+        }
+    }
+}
+
+// methods
+impl Registry {
+    /// Adds `solver`, replacing any solver already registered under its name.
+    pub fn register(&mut self, solver: Box<dyn Solver>) {
+        match self.position(solver.name()) {
+            Some(i) => self.solvers[i] = solver,
+            None => self.solvers.push(solver),
+        }
+    }
+
+    /// The solver registered as `name`.
+    pub fn get(&self, name: &str) -> Option<&dyn Solver> {
+        self.position(name).map(|i| self.solvers[i].as_ref())
+    }
+
+    /// The solver registered as `name`, to change its parameters.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut (dyn Solver + 'static)> {
+        match self.position(name) {
+            Some(i) => Some(self.solvers[i].as_mut()),
+            None => None,
+        }
+    }
+
+    /// Registered solvers in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Solver> {
+        self.solvers.iter().map(|s| s.as_ref())
+    }
+
+    /// Names of the registered solvers in registration order.
+    pub fn names(&self) -> Vec<&str> {
+        self.iter().map(|s| s.name()).collect()
+    }
+
+    /// The registered name closest to `name`, if any is a plausible typo.
+    pub fn suggest(&self, name: &str) -> Option<&str> {
+        self.iter()
+            .map(|s| (edit_distance(name, s.name()), s.name()))
+            .filter(|&(d, n)| d <= usize::max(2, n.len() / 2))
+            .min_by_key(|&(d, _)| d)
+            .map(|(_, n)| n)
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.solvers.iter().position(|s| s.name() == name)
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        let mut registry = Registry::new();
+        registry.register(Box::new(Bfs));
+        registry.register(Box::new(Dfs));
+        registry.register(Box::new(Dijkstra));
+        registry.register(Box::new(Dijkstra2));
+        registry.register(Box::new(AStar::default()));
+        registry.register(Box::new(KeyBfs));
+        registry.register(Box::new(KeyAStar::default()));
+        registry
+    }
+}
+
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diag = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diag
+            } else {
+                1 + usize::min(diag, usize::min(above, row[j]))
+            };
+            diag = above;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// See [`pathfinder::bfs`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Bfs;
+
+impl Solver for Bfs {
+    fn name(&self) -> &str {
+        "bfs"
+    }
+
+    fn description(&self) -> &str {
+        "breadth-first search, fewest nodes on the path"
+    }
+
+    fn solve_observed(&self, graph: &dyn Graph, observer: &mut dyn SearchObserver) -> SolveResult {
+        pathfinder::bfs_observed(graph, observer)
+    }
+}
+
+/// See [`pathfinder::dfs`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dfs;
+
+impl Solver for Dfs {
+    fn name(&self) -> &str {
+        "dfs"
+    }
+
+    fn description(&self) -> &str {
+        "depth-first search, quick but not shortest"
+    }
+
+    fn solve_observed(&self, graph: &dyn Graph, observer: &mut dyn SearchObserver) -> SolveResult {
+        pathfinder::dfs_observed(graph, observer)
+    }
+}
+
+/// See [`pathfinder::dijk`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dijkstra;
+
+impl Solver for Dijkstra {
+    fn name(&self) -> &str {
+        "dijk"
+    }
+
+    fn description(&self) -> &str {
+        "Dijkstra's algorithm on a binary heap, shortest path in pixels"
+    }
+
+    fn solve_observed(&self, graph: &dyn Graph, observer: &mut dyn SearchObserver) -> SolveResult {
+        pathfinder::dijk_observed(graph, observer)
+    }
+}
+
+/// See [`pathfinder::dijk2`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Dijkstra2;
+
+impl Solver for Dijkstra2 {
+    fn name(&self) -> &str {
+        "dijk2"
+    }
+
+    fn description(&self) -> &str {
+        "Dijkstra's algorithm on an indexed priority queue"
+    }
+
+    fn solve_observed(&self, graph: &dyn Graph, observer: &mut dyn SearchObserver) -> SolveResult {
+        pathfinder::dijk2_observed(graph, observer)
+    }
+}
+
+/// See [`pathfinder::astar_observed`].
+#[derive(Debug, Clone, Copy)]
+pub struct AStar {
+    heuristic: Heuristic,
+}
+
+impl AStar {
+    pub fn new(heuristic: Heuristic) -> AStar {
+        AStar { heuristic }
+    }
+}
+
+impl Default for AStar {
+    fn default() -> AStar {
+        AStar::new(Heuristic::Octile)
+    }
+}
+
+impl Solver for AStar {
+    fn name(&self) -> &str {
+        "astar"
+    }
+
+    fn description(&self) -> &str {
+        "A* search, shortest path in pixels guided by a heuristic"
+    }
+
+    fn parameters(&self) -> &[Parameter] {
+        HEURISTIC_PARAMETERS
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), ParameterError> {
+        self.heuristic = parse_heuristic(self.name(), name, value)?;
+        Ok(())
+    }
+
+    fn solve_observed(&self, graph: &dyn Graph, observer: &mut dyn SearchObserver) -> SolveResult {
+        pathfinder::astar_observed(graph, self.heuristic, observer)
+    }
+}
+
+/// The parameters of [`AStar`] and [`KeyAStar`].
+const HEURISTIC_PARAMETERS: &[Parameter] = &[Parameter {
+    name: "heuristic",
+    description: "distance estimate to the exit: octile, manhattan or zero",
+    default: "octile",
+}];
+
+/// The heuristic set by the parameter `name` of the solver called `solver`,
+/// which must be `heuristic`, to `value`.
+fn parse_heuristic(solver: &str, name: &str, value: &str) -> Result<Heuristic, ParameterError> {
+    match (name, value) {
+        ("heuristic", "octile") => Ok(Heuristic::Octile),
+        ("heuristic", "manhattan") => Ok(Heuristic::Manhattan),
+        ("heuristic", "zero") => Ok(Heuristic::Zero),
+        ("heuristic", _) => Err(ParameterError::InvalidValue {
+            name: name.to_owned(),
+            value: value.to_owned(),
+        }),
+        _ => Err(ParameterError::Unknown {
+            solver: solver.to_owned(),
+            name: name.to_owned(),
+        }),
+    }
+}
+
+/// See [`pathfinder::keys_bfs`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeyBfs;
+
+impl Solver for KeyBfs {
+    fn name(&self) -> &str {
+        "keybfs"
+    }
+
+    fn description(&self) -> &str {
+        "breadth-first search through doors, picking up the keys on the way"
+    }
+
+    fn opens_doors(&self) -> bool {
+        true
+    }
+
+    fn solve_observed(&self, graph: &dyn Graph, observer: &mut dyn SearchObserver) -> SolveResult {
+        // only mazes have doors
+        match graph.as_maze() {
+            Some(maze) => pathfinder::keys_bfs_observed(maze, observer),
+            None => pathfinder::bfs_observed(graph, observer),
+        }
+    }
+}
+
+/// See [`pathfinder::keys_astar_observed`].
+#[derive(Debug, Clone, Copy)]
+pub struct KeyAStar {
+    heuristic: Heuristic,
+}
+
+impl KeyAStar {
+    pub fn new(heuristic: Heuristic) -> KeyAStar {
+        KeyAStar { heuristic }
+    }
+}
+
+impl Default for KeyAStar {
+    fn default() -> KeyAStar {
+        KeyAStar::new(Heuristic::Octile)
+    }
+}
+
+impl Solver for KeyAStar {
+    fn name(&self) -> &str {
+        "keyastar"
+    }
+
+    fn description(&self) -> &str {
+        "A* search through doors, picking up the keys on the way"
+    }
+
+    fn opens_doors(&self) -> bool {
+        true
+    }
+
+    fn parameters(&self) -> &[Parameter] {
+        HEURISTIC_PARAMETERS
+    }
+
+    fn set_parameter(&mut self, name: &str, value: &str) -> Result<(), ParameterError> {
+        self.heuristic = parse_heuristic(self.name(), name, value)?;
+        Ok(())
+    }
+
+    fn solve_observed(&self, graph: &dyn Graph, observer: &mut dyn SearchObserver) -> SolveResult {
+        match graph.as_maze() {
+            Some(maze) => pathfinder::keys_astar_observed(maze, self.heuristic, observer),
+            None => pathfinder::astar_observed(graph, self.heuristic, observer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_distance_counts_single_edits() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("", "bfs"), 3);
+        assert_eq!(edit_distance("bfs", "bfs"), 0);
+        assert_eq!(edit_distance("bsf", "bfs"), 2);
+        assert_eq!(edit_distance("dijk", "dijk2"), 1);
+        assert_eq!(edit_distance("astra", "astar"), 2);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn suggests_the_closest_plausible_name() {
+        let registry = Registry::default();
+        assert_eq!(registry.suggest("bsf"), Some("bfs"));
+        assert_eq!(registry.suggest("astra"), Some("astar"));
+        assert_eq!(registry.suggest("keyastr"), Some("keyastar"));
+        // ties go to the name registered first
+        assert_eq!(registry.suggest("dijk3"), Some("dijk"));
+        assert_eq!(registry.suggest("floodfill"), None);
+        assert_eq!(Registry::new().suggest("bfs"), None);
+    }
+
+    #[test]
+    fn both_astars_take_a_heuristic() {
+        let mut registry = Registry::default();
+        for name in &["astar", "keyastar"] {
+            let solver = registry.get_mut(name).unwrap();
+            assert_eq!(solver.parameters(), HEURISTIC_PARAMETERS);
+            assert!(solver.set_parameter("heuristic", "manhattan").is_ok());
+            assert!(matches!(
+                solver.set_parameter("heuristic", "euclid"),
+                Err(ParameterError::InvalidValue { .. })
+            ));
+            match solver.set_parameter("weight", "2") {
+                Err(ParameterError::Unknown { solver, .. }) => assert_eq!(solver, *name),
+                other => panic!("{:?}", other),
+            }
+        }
+    }
+}
@@ -0,0 +1,21 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// Reasons a [`Solver`](super::Solver) rejected a parameter.
+#[derive(Debug)]
+pub enum ParameterError {
+    Unknown { solver: String, name: String },
+    InvalidValue { name: String, value: String },
+}
+
+impl Display for ParameterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use ParameterError::*;
+        match self {
+            Unknown { solver, name } => write!(f, "{} has no parameter named {}", solver, name),
+            InvalidValue { name, value } => write!(f, "{} is not a valid {}", value, name),
+        }
+    }
+}
+
+impl Error for ParameterError {}
@@ -1,13 +1,298 @@
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
 
+use maze_solver::maze::node::Position;
+
 #[derive(Debug)]
-pub struct InvalidAlgorithmError(pub String);
+pub struct InvalidAlgorithmError {
+    pub name: String,
+    pub suggestion: Option<String>,
+    pub valid: Vec<String>,
+}
 
 impl Display for InvalidAlgorithmError {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "{} is not a valid algorithm.", self.0)
+        write!(f, "{} is not a valid algorithm.", self.name)?;
+        if let Some(s) = &self.suggestion {
+            write!(f, " Did you mean {}?", s)?;
+        }
+        write!(f, "\nValid algorithms: {}", self.valid.join(", "))
     }
 }
 
 impl Error for InvalidAlgorithmError {}
+
+#[derive(Debug)]
+pub struct InvalidParameterError(pub String);
+
+impl Display for InvalidParameterError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is not a valid parameter, expected name=value.",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidParameterError {}
+
+#[derive(Debug)]
+pub struct InvalidColorError(pub String);
+
+impl Display for InvalidColorError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is not a valid color, expected hex RGB like ff0000.",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidColorError {}
+
+#[derive(Debug)]
+pub struct InvalidPositionError(pub String);
+
+impl Display for InvalidPositionError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is not a valid position, expected x,y or level:x,y.",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidPositionError {}
+
+#[derive(Debug)]
+pub struct InvalidLevelError {
+    pub level: usize,
+    pub pos: Position,
+    pub levels: usize,
+    pub size: (u32, u32),
+}
+
+impl Display for InvalidLevelError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}:{},{} is not on the maze, which has {} level(s) of {}x{} pixels counted from 0.",
+            self.level,
+            self.pos.col(),
+            self.pos.row(),
+            self.levels,
+            self.size.0,
+            self.size.1
+        )
+    }
+}
+
+impl Error for InvalidLevelError {}
+
+#[derive(Debug)]
+pub struct InvalidTerrainError(pub String);
+
+impl Display for InvalidTerrainError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is not a valid terrain cost, expected RRGGBB=cost.",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidTerrainError {}
+
+#[derive(Debug)]
+pub struct InvalidKeyError(pub String);
+
+impl Display for InvalidKeyError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is not a valid key, expected KEY=DOOR as two hex RGB colors.",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidKeyError {}
+
+#[derive(Debug)]
+pub struct InvalidOneWayError(pub String);
+
+impl Display for InvalidOneWayError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is not a valid one-way color, expected direction=RRGGBB with a direction of north, east, south or west.",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidOneWayError {}
+
+#[derive(Debug)]
+pub struct InvalidMazeError(pub usize);
+
+impl Display for InvalidMazeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self.0 {
+            1 => write!(f, "Found 1 problem in the maze."),
+            n => write!(f, "Found {} problems in the maze.", n),
+        }
+    }
+}
+
+impl Error for InvalidMazeError {}
+
+#[derive(Debug)]
+pub struct UnsupportedStreamError(pub String);
+
+impl Display for UnsupportedStreamError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} cannot be streamed, only png, pbm, pgm, ppm and pnm files can.",
+            self.0
+        )
+    }
+}
+
+impl Error for UnsupportedStreamError {}
+
+#[derive(Debug)]
+pub struct MultipleOutputsError(pub usize, pub String);
+
+impl Display for MultipleOutputsError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} solved images cannot all be written to {}, choose one algorithm.",
+            self.0, self.1
+        )
+    }
+}
+
+impl Error for MultipleOutputsError {}
+
+#[derive(Debug)]
+pub struct StreamFormatError(pub String);
+
+impl Display for StreamFormatError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Streamed solutions are always png, {} is not supported.",
+            self.0
+        )
+    }
+}
+
+impl Error for StreamFormatError {}
+
+#[derive(Debug)]
+pub struct InvalidCellSizeError(pub String);
+
+impl Display for InvalidCellSizeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is not a valid cell size, expected a number of pixels or auto.",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidCellSizeError {}
+
+#[derive(Debug)]
+pub struct InvalidFormatError(pub String);
+
+impl Display for InvalidFormatError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is not a valid format, expected png, bmp, jpeg, tiff, ppm, svg or gif.",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidFormatError {}
+
+#[derive(Debug)]
+pub struct LevelsFormatError(pub String);
+
+impl Display for LevelsFormatError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Mazes with levels cannot be saved as {}.", self.0)
+    }
+}
+
+impl Error for LevelsFormatError {}
+
+#[derive(Debug)]
+pub struct GraphFormatError(pub String);
+
+impl Display for GraphFormatError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Graph files are solved as text and cannot be saved as {}.",
+            self.0
+        )
+    }
+}
+
+impl Error for GraphFormatError {}
+
+#[derive(Debug)]
+pub struct TextFormatError(pub String);
+
+impl Display for TextFormatError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Text mazes are solved as text and cannot be saved as {}.",
+            self.0
+        )
+    }
+}
+
+impl Error for TextFormatError {}
+
+#[derive(Debug)]
+pub struct InvalidTemplateError(pub String);
+
+impl Display for InvalidTemplateError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} is not a valid output name, placeholders are {{stem}}, {{algo}}, {{len}}, {{cost}}, {{w}} and {{h}}.",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidTemplateError {}
+
+#[derive(Debug)]
+pub struct LockedDoorsError(pub Vec<String>);
+
+impl Display for LockedDoorsError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(
+            f,
+            "The maze has doors, which {} would walk through. Solve it with keybfs or keyastar.",
+            self.0.join(", ")
+        )
+    }
+}
+
+impl Error for LockedDoorsError {}
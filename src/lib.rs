@@ -0,0 +1,24 @@
+//! Solve black and white maze images.
+//!
+//! A [`Maze`](maze::Maze) is built from an RGB image, turning every junction,
+//! corner and dead end into a [`Node`](maze::node::Node). The functions in
+//! [`pathfinder`] search that graph from the start node to the exit and return
+//! the pixel positions along the way, which [`render::draw_path`] can paint
+//! back onto the image. They run on any [`Graph`](pathfinder::Graph), such as
+//! a [`Network`](network::Network) loaded from a road network file.
+//!
+//! ```no_run
+//! use maze_solver::{maze::Maze, pathfinder, render};
+//!
+//! let mut img = image::open("img/tiny.png").unwrap().to_rgb();
+//! let maze = Maze::new(&img).unwrap();
+//! if let Some(path) = pathfinder::bfs(&maze).path {
+//!     render::draw_path(&mut img, &path);
+//! }
+//! ```
+
+pub mod algos;
+pub mod maze;
+pub mod network;
+pub mod pathfinder;
+pub mod render;
@@ -1,6 +1,5 @@
 mod cli_errors;
-mod maze;
-mod pathfinder;
+mod output;
 
 use image::{GenericImageView, Rgb, RgbImage};
 use num::Num;
@@ -10,22 +9,64 @@ use std::boxed::Box;
 use std::error::Error;
 use std::ffi::OsString;
 use std::fs;
-use std::io::{self, ErrorKind};
-use std::path::PathBuf;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::str;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time;
-use std::u32;
 
-use maze::node::Position;
-use maze::Maze;
+use maze_solver::algos::{self, Registry, Solver};
+use maze_solver::maze::classify::{Polarity, WallClassifier};
+use maze_solver::maze::keys::Keys;
+use maze_solver::maze::levels::Levels;
+use maze_solver::maze::node::Position;
+use maze_solver::maze::oneway::{Direction, OneWay};
+use maze_solver::maze::portal::Portals;
+use maze_solver::maze::scale;
+use maze_solver::maze::stream::{PngRows, PnmRows, RowSource};
+use maze_solver::maze::terrain::Terrain;
+use maze_solver::maze::text::{self, TextMaze};
+use maze_solver::maze::{self, Connectivity, Endpoint, Maze, MazeOptions};
+use maze_solver::network::Network;
+use maze_solver::pathfinder::{Recorder, SolveResult};
+use maze_solver::render;
+use maze_solver::render::animate::{self, AnimationOptions};
+use maze_solver::render::svg::{self, SvgOptions};
+
+use output::{Format, Naming};
+
+/// Whether solutions are written to stdout, which moves progress messages to
+/// stderr.
+static TO_STDOUT: AtomicBool = AtomicBool::new(false);
+
+/// `println!` for progress messages, which go to stderr while stdout carries
+/// a solution.
+macro_rules! status {
+    ($($arg:tt)*) => {
+        if TO_STDOUT.load(Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
 
 #[derive(Debug, StructOpt)]
 struct Opt {
-    /// Alogrithm. Options: bfs, dfs, dijk, astar
-    #[structopt(short = "a", long = "algo", default_value = "bfs")]
-    pub algo: String,
-    /// Output directory
+    /// Algorithm. See the list of algorithms below. Several algorithms
+    /// separated by commas are solved in parallel
+    #[structopt(
+        short = "a",
+        long = "algo",
+        default_value = "bfs",
+        raw(use_delimiter = "true", number_of_values = "1")
+    )]
+    pub algo: Vec<String>,
+    /// Algorithm parameter as name=value. May be repeated
+    #[structopt(short = "p", long = "param", raw(number_of_values = "1"))]
+    pub params: Vec<String>,
+    /// Output directory, or - to write the solution to stdout
     #[structopt(
         short = "o",
         long = "output",
@@ -33,7 +74,179 @@ struct Opt {
         parse(from_os_str)
     )]
     pub output: PathBuf,
-    /// Input image
+    /// Name of each solved file in the output directory, without its
+    /// extension. Placeholders: {stem}, {algo}, {len}, {cost}, {w} and {h}
+    #[structopt(long = "output-name", default_value = "{stem}-{algo}")]
+    pub output_name: String,
+    /// Write the solution to this file instead of the output directory
+    #[structopt(long = "output-file", parse(from_os_str))]
+    pub output_file: Option<PathBuf>,
+    /// Image format of the solution: png, bmp, jpeg, tiff, ppm, svg, or gif
+    /// for an animation of the search. Taken from the extension of
+    /// --output-file by default, otherwise png
+    #[structopt(long = "format", parse(try_from_str = "parse_format"))]
+    pub format: Option<Format>,
+    /// Width of the path in an svg solution, in pixels of the maze
+    #[structopt(long = "stroke-width", default_value = "1")]
+    pub stroke_width: f32,
+    /// Mark the nodes the search reached in an svg solution
+    #[structopt(long = "show-visited")]
+    pub show_visited: bool,
+    /// Nodes expanded between two frames of a gif animation. By default the
+    /// animation has about 200 frames
+    #[structopt(long = "frame-stride")]
+    pub frame_stride: Option<usize>,
+    /// Width and height in the gif animation of a pixel of the maze
+    #[structopt(long = "frame-scale", default_value = "1")]
+    pub frame_scale: u32,
+    /// Wall color as hex RGB, e.g. 000000
+    #[structopt(
+        long = "wall-color",
+        default_value = "000000",
+        parse(try_from_str = "parse_color")
+    )]
+    pub wall_color: Rgb<u8>,
+    /// How far each channel may differ from the wall and marker colors
+    #[structopt(long = "tolerance", default_value = "0")]
+    pub tolerance: u8,
+    /// Treat pixels darker than this luminance (0-255) as walls instead of
+    /// matching the wall color
+    #[structopt(long = "threshold")]
+    pub threshold: Option<u8>,
+    /// Walls are the light pixels instead of the dark ones
+    #[structopt(long = "invert", raw(conflicts_with = r#""auto_polarity""#))]
+    pub invert: bool,
+    /// Detect whether walls are dark or light from the image border
+    #[structopt(long = "auto-polarity")]
+    pub auto_polarity: bool,
+    /// Start at this pixel, given as x,y, or as level:x,y on a maze with
+    /// levels
+    #[structopt(
+        long = "start",
+        parse(try_from_str = "parse_level_position"),
+        raw(conflicts_with = r#""start_color""#)
+    )]
+    pub start: Option<LevelPosition>,
+    /// End at this pixel, given as x,y, or as level:x,y on a maze with
+    /// levels. May be repeated for several exits
+    #[structopt(
+        long = "end",
+        parse(try_from_str = "parse_level_position"),
+        raw(number_of_values = "1")
+    )]
+    pub end: Vec<LevelPosition>,
+    /// Start at the first pixel of this hex RGB color
+    #[structopt(long = "start-color", parse(try_from_str = "parse_color"))]
+    pub start_color: Option<Rgb<u8>>,
+    /// End at the first pixel of this hex RGB color
+    #[structopt(long = "end-color", parse(try_from_str = "parse_color"))]
+    pub end_color: Option<Rgb<u8>>,
+    /// Start at the border opening with this index, see --openings
+    #[structopt(
+        long = "start-opening",
+        raw(conflicts_with_all = r#"&["start", "start_color"]"#)
+    )]
+    pub start_opening: Option<usize>,
+    /// End at the border opening with this index, see --openings. May be
+    /// repeated for several exits
+    #[structopt(long = "end-opening", raw(number_of_values = "1"))]
+    pub end_opening: Vec<usize>,
+    /// Use every border opening except the start as an exit
+    #[structopt(long = "all-exits")]
+    pub all_exits: bool,
+    /// Make darker pixels cost more to cross, up to this cost for black
+    #[structopt(long = "gray-cost", raw(conflicts_with = r#""terrain""#))]
+    pub gray_cost: Option<u32>,
+    /// Cost of crossing pixels of a color, given as RRGGBB=cost. May be
+    /// repeated. Other pixels cost 1
+    #[structopt(
+        long = "terrain",
+        parse(try_from_str = "parse_terrain"),
+        raw(number_of_values = "1")
+    )]
+    pub terrain: Vec<(Rgb<u8>, u32)>,
+    /// Color of a pair of portal pixels, as hex RGB. May be repeated
+    #[structopt(
+        long = "portal",
+        parse(try_from_str = "parse_color"),
+        raw(number_of_values = "1")
+    )]
+    pub portal: Vec<Rgb<u8>>,
+    /// Treat every color found on exactly two pixels as a portal pair
+    #[structopt(long = "auto-portals")]
+    pub auto_portals: bool,
+    /// Cost of jumping through a portal
+    #[structopt(long = "portal-cost", default_value = "0")]
+    pub portal_cost: u32,
+    /// Color of pixels only passable in one direction, given as
+    /// direction=RRGGBB, e.g. north=ff0000. May be repeated
+    #[structopt(
+        long = "one-way",
+        parse(try_from_str = "parse_one_way"),
+        raw(number_of_values = "1")
+    )]
+    pub one_way: Vec<(Direction, Rgb<u8>)>,
+    /// Colors of a key and the door it opens, given as KEY=DOOR in hex RGB,
+    /// e.g. ff0000=800000. May be repeated. Solve with keybfs or keyastar
+    #[structopt(
+        long = "key",
+        parse(try_from_str = "parse_key"),
+        raw(number_of_values = "1")
+    )]
+    pub keys: Vec<(Rgb<u8>, Rgb<u8>)>,
+    /// Image of a further floor, stacked under the input image. May be
+    /// repeated, top floor first
+    #[structopt(long = "level", parse(from_os_str), raw(number_of_values = "1"))]
+    pub levels: Vec<PathBuf>,
+    /// Color of stair pixels, which lead to the same pixel on the next floor
+    /// if it is a stair too
+    #[structopt(
+        long = "stair-color",
+        default_value = "ffff00",
+        parse(try_from_str = "parse_color")
+    )]
+    pub stair_color: Rgb<u8>,
+    /// Cost of taking the stairs between two floors
+    #[structopt(long = "stair-cost", default_value = "1")]
+    pub stair_cost: u32,
+    /// Allow diagonal steps between open pixels, costing √2 times a straight
+    /// step
+    #[structopt(long = "diagonal")]
+    pub diagonal: bool,
+    /// Only step diagonally when both pixels beside the step are open
+    #[structopt(long = "no-corner-cutting", raw(requires = r#""diagonal""#))]
+    pub no_corner_cutting: bool,
+    /// Size in pixels of one maze cell, or auto to detect it from the image
+    #[structopt(long = "cell-size", parse(try_from_str = "parse_cell_size"))]
+    pub cell_size: Option<CellSize>,
+    /// Check the maze for problems, report them and exit
+    #[structopt(long = "validate")]
+    pub validate: bool,
+    /// List the openings in the border of the maze and exit
+    #[structopt(long = "openings")]
+    pub openings: bool,
+    /// Read the image one row at a time while building the maze and drawing
+    /// the solution, instead of loading it whole. For PNG and Netpbm files
+    #[structopt(
+        long = "stream",
+        raw(
+            conflicts_with_all = r#"&["auto_polarity", "auto_portals", "levels", "cell_size", "validate", "openings"]"#
+        )
+    )]
+    pub stream: bool,
+    /// Node to start from in a graph file. The first node by default
+    #[structopt(long = "source")]
+    pub source: Option<String>,
+    /// Node to end at in a graph file. May be repeated. The last node by
+    /// default
+    #[structopt(long = "target", raw(number_of_values = "1"))]
+    pub target: Vec<String>,
+    /// Edges in a CSV graph file only go from source to target
+    #[structopt(long = "directed")]
+    pub directed: bool,
+    /// Input image, a text maze if the file ends in .txt, or a graph file
+    /// ending in .gr, .csv, .dot or .gv. - reads an image or text maze from
+    /// stdin
     #[structopt(parse(from_os_str))]
     pub image: PathBuf,
 }
@@ -45,152 +258,971 @@ fn main() {
     }
 }
 
-fn run() -> Result<(), Box<dyn Error>> {
-    const NS_S: u128 = 1_000_000_000;
+const NS_S: u128 = 1_000_000_000;
 
+fn run() -> Result<(), Box<dyn Error>> {
     let time_total = time::Instant::now();
 
-    let opt = Opt::from_args();
-    let algo_string = opt.algo;
-    let img_path = opt.image;
-    let out_path = opt.output;
+    let mut registry = Registry::default();
+    let algo_help = algo_help(&registry);
+    let opt = Opt::from_clap(&Opt::clap().after_help(algo_help.as_str()).get_matches());
+    let algo_strings = &opt.algo;
+    let img_path = &opt.image;
 
-    if !img_path.exists() {
+    let from_stdin = is_stdio(img_path);
+    let to_stdout = is_stdio(&opt.output);
+    TO_STDOUT.store(to_stdout, Ordering::Relaxed);
+    if from_stdin && opt.stream {
+        return Err(Box::new(cli_errors::UnsupportedStreamError(
+            "stdin".to_owned(),
+        )));
+    }
+
+    if !from_stdin && !img_path.exists() {
         return Err(Box::new(io::Error::new(
             ErrorKind::NotFound,
             "File path does not exists.",
         )));
     }
-    if !img_path.is_file() {
-        return Err(Box::new(io::Error::new(
-            ErrorKind::Other,
-            "File path is not a file.",
+    if !from_stdin && !img_path.is_file() {
+        return Err(Box::new(io::Error::other("File path is not a file.")));
+    }
+    let algos = get_algos(&mut registry, algo_strings, &opt.params)?;
+    // one image per algorithm and floor
+    let outputs = algos.len() * (opt.levels.len() + 1);
+    let single_target = match &opt.output_file {
+        Some(file) => Some(file.display().to_string()),
+        None if to_stdout => Some("stdout".to_owned()),
+        None => None,
+    };
+    if let Some(target) = single_target {
+        if outputs > 1 {
+            return Err(Box::new(cli_errors::MultipleOutputsError(outputs, target)));
+        }
+    }
+    // placeholders are checked before any work is done
+    output::expand(&opt.output_name, &Naming::default())?;
+    let format = image_format(&opt);
+    if opt.stream && format != Format::Png {
+        return Err(Box::new(cli_errors::StreamFormatError(
+            format.extension().to_owned(),
         )));
     }
-    let algo = get_algo(&algo_string)?;
+    if format.is_drawing() && !opt.levels.is_empty() {
+        return Err(Box::new(cli_errors::LevelsFormatError(
+            format.extension().to_owned(),
+        )));
+    }
+    if !from_stdin && is_network(img_path) {
+        if let Some(format) = chosen_format(&opt) {
+            return Err(Box::new(cli_errors::GraphFormatError(
+                format.extension().to_owned(),
+            )));
+        }
+        return solve_network(&opt, &algos, time_total);
+    }
+
+    // stdin is sniffed: anything that is not an image is read as text
+    let mut input = None;
+    if from_stdin {
+        let mut bytes = Vec::new();
+        io::stdin().read_to_end(&mut bytes)?;
+        input = Some(bytes);
+    }
+    let is_text = match &input {
+        Some(bytes) => image::guess_format(bytes).is_err(),
+        None => img_path.extension().is_some_and(|e| e == "txt"),
+    };
+    if is_text {
+        if let Some(format) = chosen_format(&opt) {
+            return Err(Box::new(cli_errors::TextFormatError(
+                format.extension().to_owned(),
+            )));
+        }
+    }
+    let input_name = match &input {
+        Some(_) => "stdin".to_owned(),
+        None => img_path.display().to_string(),
+    };
 
     let mut img: image::RgbImage;
-    {
-        println!("Loading Image");
-        let dyn_img = image::open(&img_path)?;
-        println!("{}", img_path.display());
-        println!(
+    let mut text_maze = None;
+    let mut source = None;
+    if opt.stream && !is_text {
+        status!("Streaming Image");
+        let rows = row_source(img_path)?;
+        status!("{}", input_name);
+        let (width, height) = rows.dimensions();
+        status!("width: {}\nheight: {}", fmt_num(width), fmt_num(height));
+        status!();
+
+        // only the rows are read, the image is never loaded whole
+        img = RgbImage::new(0, 0);
+        source = Some(rows);
+    } else if is_text {
+        status!("Loading Text");
+        let tm = match input {
+            Some(bytes) => TextMaze::parse(&String::from_utf8(bytes)?)?,
+            None => TextMaze::parse(&fs::read_to_string(img_path)?)?,
+        };
+        status!("{}", input_name);
+        status!(
+            "width: {}\nheight: {}",
+            fmt_num(tm.image().width()),
+            fmt_num(tm.image().height())
+        );
+        status!();
+
+        img = tm.image().clone();
+        text_maze = Some(tm);
+    } else {
+        status!("Loading Image");
+        let dyn_img = match input {
+            Some(bytes) => image::load_from_memory(&bytes)?,
+            None => image::open(img_path)?,
+        };
+        status!("{}", input_name);
+        status!(
             "width: {}\nheight: {}",
             fmt_num(dyn_img.width()),
             fmt_num(dyn_img.height())
         );
         let (color_type, depth) = colortype_to_str(dyn_img.color());
-        println!("color type: {}\ndepth: {}", color_type, depth);
-        println!();
+        status!("color type: {}\ndepth: {}", color_type, depth);
+        status!();
 
         img = dyn_img.to_rgb();
     }
 
-    println!("Creating Maze");
+    // the size of the input, which solved images keep
+    let size = match &source {
+        Some(rows) => rows.dimensions(),
+        None => img.dimensions(),
+    };
+
+    status!("Creating Maze");
     let time0 = time::Instant::now();
-    let maze = Maze::new(&img)?;
+    let mut options = match &text_maze {
+        Some(tm) => tm.options(),
+        None => MazeOptions {
+            walls: wall_classifier(&opt, &img),
+            ..MazeOptions::default()
+        },
+    };
+    if opt.diagonal {
+        options.connectivity = Connectivity::Eight {
+            corner_cutting: !opt.no_corner_cutting,
+        };
+    }
+    let mut floors = Vec::new();
+    let mut levels = None;
+    if !opt.levels.is_empty() {
+        floors.push(img.clone());
+        for path in &opt.levels {
+            status!("Loading Level");
+            status!("{}", path.display());
+            floors.push(image::open(path)?.to_rgb());
+        }
+        let stacked = Levels::stack(&floors, &options.walls)?;
+        status!("Levels: {}", fmt_num(stacked.count()));
+        options.level_height = Some(stacked.dimensions().1);
+        options
+            .links
+            .extend(stacked.stairs(opt.stair_color, opt.tolerance, opt.stair_cost));
+        img = stacked.image().clone();
+        levels = Some(stacked);
+    }
+    if let Some(max) = opt.gray_cost {
+        options.terrain = Terrain::Grayscale { max };
+    } else if !opt.terrain.is_empty() {
+        options.terrain = Terrain::Palette {
+            colors: opt.terrain.clone(),
+            tolerance: opt.tolerance,
+        };
+    }
+    options.one_way = OneWay {
+        colors: opt.one_way.clone(),
+        tolerance: opt.tolerance,
+    };
+    options.keys = Keys {
+        pairs: opt.keys.clone(),
+        tolerance: opt.tolerance,
+    };
+    options.portals = Portals {
+        colors: opt.portal.clone(),
+        tolerance: opt.tolerance,
+        cost: opt.portal_cost,
+    };
+    if opt.auto_portals {
+        // a pair of one-way, key or door pixels is not a portal
+        let colors: Vec<Rgb<u8>> = Portals::detect(&img, &options.walls)
+            .into_iter()
+            .filter(|&c| !options.one_way.is_one_way(c) && !options.keys.is_key_or_door(c))
+            .collect();
+        status!("Detected portals: {}", fmt_num(colors.len()));
+        for color in colors {
+            if !options.portals.colors.contains(&color) {
+                options.portals.colors.push(color);
+            }
+        }
+    }
+    let start = opt
+        .start
+        .map(|pos| stacked_position(pos, levels.as_ref(), img.dimensions()))
+        .transpose()?;
+    if let Some(start) = endpoint(start, opt.start_color, opt.start_opening, opt.tolerance) {
+        options.start = start;
+    }
+    let ends = end_endpoints(&opt, levels.as_ref(), img.dimensions())?;
+    if !ends.is_empty() {
+        options.ends = ends;
+    }
+
+    if opt.validate {
+        let problems = maze::validate(&img, &options);
+        for problem in &problems {
+            status!("{}", problem);
+        }
+        if !problems.is_empty() {
+            return Err(Box::new(cli_errors::InvalidMazeError(problems.len())));
+        }
+        status!("Maze is valid");
+        return Ok(());
+    }
+
+    let cell = match opt.cell_size {
+        Some(CellSize::Pixels(cell)) => cell.max(1),
+        Some(CellSize::Auto) if !is_text && levels.is_none() => {
+            scale::detect_cell_size(&img, &options.walls)
+        }
+        _ => 1,
+    };
+    if cell > 1 {
+        status!("Cell size: {}", fmt_num(cell));
+        let downscale = |pos| match &levels {
+            Some(levels) => levels.downscale(pos, cell),
+            None => scale::downscale(pos, cell),
+        };
+        options.start = downscale_endpoint(options.start, downscale);
+        for end in &mut options.ends {
+            *end = downscale_endpoint(*end, downscale);
+        }
+    }
+    let cells = match &levels {
+        // each floor is downsampled on its own, so the stairs and the
+        // floor height are found again in cells
+        Some(levels) if cell > 1 => {
+            let small = levels.downsample(cell);
+            options.level_height = Some(small.dimensions().1);
+            options.links = small.stairs(opt.stair_color, opt.tolerance, opt.stair_cost);
+            small.image().clone()
+        }
+        None if cell > 1 => scale::downsample(&img, cell),
+        _ => img.clone(),
+    };
+    // maps a position of the maze back to the input pixels
+    let to_pixels = |pos| match &levels {
+        Some(levels) => levels.upscale(pos, cell),
+        None => scale::upscale(pos, cell),
+    };
+
+    if opt.openings {
+        let openings = maze::openings(&cells, &options);
+        status!("Openings: {}", fmt_num(openings.len()));
+        for (i, &pos) in openings.iter().enumerate() {
+            let pos = to_pixels(pos);
+            match levels.as_ref().and_then(|l| l.level_of(pos)) {
+                Some((level, pos)) => status!("{}: {}:{},{}", i, level, pos.col(), pos.row()),
+                None => status!("{}: {},{}", i, pos.col(), pos.row()),
+            }
+        }
+        return Ok(());
+    }
+    let maze = match &mut source {
+        Some(rows) => Maze::from_rows(rows.as_mut(), &options)?,
+        None => Maze::with_options(&cells, &options)?,
+    };
     let dur0 = time0.elapsed().as_nanos();
-    println!("Time elapsed: {}.{:09}", dur0 / NS_S, dur0 % NS_S);
-    println!("Node Count: {}", fmt_num(maze.count()));
-    println!();
-
-    println!("Solving Maze");
-    let time1 = time::Instant::now();
-    let (maybe_path, count) = algo(&maze);
-    let dur1 = time1.elapsed().as_nanos();
-    println!("Time elapsed: {}.{:09}", dur1 / NS_S, dur1 % NS_S);
-    println!("Nodes explored: {}", fmt_num(count));
-
-    if let Some(maze_path) = maybe_path {
-        println!("Path found\nLength: {}", fmt_num(maze_path.len()));
-        println!();
-        draw_path(&mut img, &maze_path);
-        save_solved(&img, &img_path, &out_path, &algo_string)?;
+    status!("Time elapsed: {}.{:09}", dur0 / NS_S, dur0 % NS_S);
+    status!("Node Count: {}", fmt_num(maze.count()));
+    status!("Openings: {}", fmt_num(maze.openings().len()));
+    status!("Exits: {}", fmt_num(maze.ends().len()));
+    status!("Links: {}", fmt_num(maze.links().len()));
+    status!();
+
+    if maze.has_doors() {
+        let blind: Vec<String> = algos
+            .iter()
+            .filter(|algo| !algo.opens_doors())
+            .map(|algo| algo.name().to_owned())
+            .collect();
+        if !blind.is_empty() {
+            return Err(Box::new(cli_errors::LockedDoorsError(blind)));
+        }
+    }
+
+    status!("Solving Maze");
+    status!();
+    // an animation needs every step of the search, so the algorithms are
+    // recorded one at a time
+    let mut recorders = vec![Recorder::new(); algos.len()];
+    let results: Vec<_> = if format == Format::Gif {
+        algos
+            .iter()
+            .zip(&mut recorders)
+            .map(|(algo, recorder)| algo.solve_observed(&maze, recorder))
+            .collect()
     } else {
-        println!("Path not found");
+        algos::solve_parallel(&maze, &algos)
+    };
+    for ((algo, result), recorder) in algos.iter().zip(results).zip(&recorders) {
+        print_stats(*algo, &result);
+
+        if let Some(maze_path) = result.path {
+            status!("Path found\nLength: {}", fmt_num(maze_path.len()));
+            if let Some(end) = result.end {
+                let exit = maze.ends().iter().position(|&e| e == end).unwrap_or(0);
+                let pos = to_pixels(maze.node(end).position());
+                match levels.as_ref().and_then(|l| l.level_of(pos)) {
+                    Some((level, pos)) => status!(
+                        "Exit: {} at {},{} on level {}",
+                        exit,
+                        pos.col(),
+                        pos.row(),
+                        level
+                    ),
+                    None => status!("Exit: {} at {},{}", exit, pos.col(), pos.row()),
+                }
+            }
+            // 8-connected costs are fixed point, with a straight step of 1
+            match maze.step_costs() {
+                (1, _) => status!("Cost: {}", fmt_num(result.cost)),
+                (straight, _) => {
+                    let cost = result.cost as f64 / f64::from(straight);
+                    status!("Cost: {}", fmt_num((cost * 100.0).round() / 100.0));
+                }
+            }
+            if !result.keys.is_empty() {
+                let keys: Vec<String> = result
+                    .keys
+                    .iter()
+                    .map(|&k| {
+                        let [r, g, b] = options.keys.pairs[k].0.data;
+                        format!("{:02x}{:02x}{:02x}", r, g, b)
+                    })
+                    .collect();
+                status!("Keys: {}", keys.join(", "));
+            }
+            status!();
+            let naming = Naming {
+                stem: file_stem(img_path),
+                algo: algo.name(),
+                len: maze_path.len(),
+                cost: result.cost,
+                width: size.0,
+                height: size.1,
+            };
+            let pixel_path: Vec<Position> = maze_path.iter().map(|&pos| to_pixels(pos)).collect();
+            if let Some(levels) = &levels {
+                let paths = Some(img_path).into_iter().chain(&opt.levels);
+                for (level, (floor, path)) in floors.iter().zip(paths).enumerate() {
+                    let mut solved = floor.clone();
+                    render::draw_level(&mut solved, levels, level, &pixel_path, &result.jumps);
+                    let naming = Naming {
+                        stem: file_stem(path),
+                        width: solved.width(),
+                        height: solved.height(),
+                        ..naming
+                    };
+                    save_solved(&solved, &opt, format, &naming)?;
+                }
+            } else if is_text {
+                let solved = text::write_solution(&img, &pixel_path);
+                save_solved_text(&solved, &opt, &naming)?;
+            } else if source.is_some() {
+                let pixels = render::path_pixels(&maze_path, &result.jumps);
+                save_streamed(&pixels, img_path, &opt, &naming)?;
+            } else if format == Format::Svg {
+                let visited = result
+                    .visited
+                    .iter()
+                    .map(|&n| to_pixels(maze.node(n).position()))
+                    .collect();
+                let jumps = &result.jumps;
+                let svg_options = SvgOptions {
+                    stroke_width: opt.stroke_width,
+                    show_visited: opt.show_visited,
+                };
+                save_drawing(&opt, format, &naming, |out| {
+                    svg::write_svg(
+                        out,
+                        &img,
+                        &options.walls,
+                        &pixel_path,
+                        jumps,
+                        &visited,
+                        &svg_options,
+                    )
+                })?;
+            } else if format == Format::Gif {
+                let animation = AnimationOptions {
+                    stride: opt.frame_stride.unwrap_or(result.expanded / 200),
+                    // the downsampled maze is scaled back up
+                    scale: opt.frame_scale * cell,
+                    ..AnimationOptions::default()
+                };
+                let jumps = &result.jumps;
+                save_drawing(&opt, format, &naming, |out| {
+                    animate::write_gif(
+                        out,
+                        &cells,
+                        &maze,
+                        recorder.events(),
+                        &maze_path,
+                        jumps,
+                        &animation,
+                    )
+                })?;
+            } else {
+                let mut solved = img.clone();
+                render::draw_path_with_jumps(&mut solved, &pixel_path, &result.jumps);
+                save_solved(&solved, &opt, format, &naming)?;
+            }
+        } else {
+            status!("Path not found");
+        }
+        status!();
     }
-    println!();
 
+    print_total(time_total);
+    Ok(())
+}
+
+/// Rows of the PNG or Netpbm file at `path`, read as they are needed.
+fn row_source(path: &Path) -> Result<Box<dyn RowSource>, Box<dyn Error>> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase);
+    let reader = BufReader::new(fs::File::open(path)?);
+    match ext.as_deref() {
+        Some("png") => Ok(Box::new(PngRows::new(reader)?)),
+        Some("pbm") | Some("pgm") | Some("ppm") | Some("pnm") => {
+            Ok(Box::new(PnmRows::new(reader)?))
+        }
+        _ => Err(Box::new(cli_errors::UnsupportedStreamError(
+            path.display().to_string(),
+        ))),
+    }
+}
+
+/// Loads the graph file at `opt.image` and searches it with `algos`, saving
+/// the names of the nodes along each path as text.
+fn solve_network(
+    opt: &Opt,
+    algos: &[&dyn Solver],
+    time_total: time::Instant,
+) -> Result<(), Box<dyn Error>> {
+    let path = &opt.image;
+    status!("Loading Graph");
+    let time0 = time::Instant::now();
+    let text = fs::read_to_string(path)?;
+    let mut network = match path.extension().and_then(|e| e.to_str()) {
+        Some("gr") => {
+            // coordinates come from the .co file next to it, if there is one
+            let co_path = path.with_extension("co");
+            let co = if co_path.is_file() {
+                Some(fs::read_to_string(co_path)?)
+            } else {
+                None
+            };
+            Network::from_dimacs(&text, co.as_deref())?
+        }
+        Some("csv") => Network::from_csv(&text, opt.directed)?,
+        _ => Network::from_dot(&text)?,
+    };
+    if let Some(source) = &opt.source {
+        network.set_start(source)?;
+    }
+    network.set_ends(&opt.target)?;
+    let dur0 = time0.elapsed().as_nanos();
+    status!("{}", path.display());
+    status!("Time elapsed: {}.{:09}", dur0 / NS_S, dur0 % NS_S);
+    status!("Node Count: {}", fmt_num(network.count()));
+    status!("Edges: {}", fmt_num(network.edge_count()));
+    status!(
+        "Coordinates: {}",
+        if network.has_coordinates() {
+            "yes"
+        } else {
+            "no"
+        }
+    );
+    status!();
+
+    status!("Solving Graph");
+    status!();
+    let results = algos::solve_parallel(&network, algos);
+    for (algo, result) in algos.iter().zip(results) {
+        print_stats(*algo, &result);
+        if result.path.is_some() {
+            status!("Path found\nLength: {}", fmt_num(result.route.len()));
+            if let Some(end) = result.end {
+                status!("Exit: {}", network.name(end));
+            }
+            status!("Cost: {}", fmt_num(result.cost));
+            status!();
+            let names: String = result
+                .route
+                .iter()
+                .map(|&n| format!("{}\n", network.name(n)))
+                .collect();
+            let naming = Naming {
+                stem: file_stem(path),
+                algo: algo.name(),
+                len: result.route.len(),
+                cost: result.cost,
+                ..Naming::default()
+            };
+            save_solved_text(&names, opt, &naming)?;
+        } else {
+            status!("Path not found");
+        }
+        status!();
+    }
+
+    print_total(time_total);
+    Ok(())
+}
+
+/// Whether `path` is a graph file rather than a maze, by its extension.
+fn is_network(path: &Path) -> bool {
+    let ext = path.extension().and_then(|e| e.to_str());
+    matches!(ext, Some("gr") | Some("csv") | Some("dot") | Some("gv"))
+}
+
+fn print_stats(algo: &dyn Solver, result: &SolveResult) {
+    let dur1 = result.elapsed.as_nanos();
+    status!("Algorithm: {}", algo.name());
+    status!("Time elapsed: {}.{:09}", dur1 / NS_S, dur1 % NS_S);
+    status!("Nodes expanded: {}", fmt_num(result.expanded));
+    status!("Nodes generated: {}", fmt_num(result.generated));
+    status!("Nodes visited: {}", fmt_num(result.visited.len()));
+    status!("Peak frontier: {}", fmt_num(result.peak_frontier));
+    status!("Reopened: {}", fmt_num(result.reopened));
+}
+
+fn print_total(time_total: time::Instant) {
     let dur_total = time_total.elapsed().as_nanos();
-    println!(
+    status!(
         "Total time elapsed: {}.{:09}",
         dur_total / NS_S,
         dur_total % NS_S
     );
-    println!();
+    status!();
+}
 
-    Ok(())
+fn wall_classifier(opt: &Opt, img: &RgbImage) -> WallClassifier {
+    let mut walls = match opt.threshold {
+        Some(threshold) => WallClassifier::Threshold {
+            threshold,
+            polarity: Polarity::DarkWalls,
+        },
+        None => WallClassifier::Color {
+            color: opt.wall_color,
+            tolerance: opt.tolerance,
+        },
+    };
+    if opt.invert {
+        walls = walls.inverted();
+    }
+    if opt.auto_polarity {
+        let polarity = Polarity::detect(img);
+        match polarity {
+            Polarity::DarkWalls => status!("Detected dark walls"),
+            Polarity::LightWalls => status!("Detected light walls"),
+        }
+        walls = walls.with_polarity(polarity);
+    }
+    walls
+}
+
+fn endpoint(
+    pos: Option<Position>,
+    color: Option<Rgb<u8>>,
+    opening: Option<usize>,
+    tolerance: u8,
+) -> Option<Endpoint> {
+    match (pos, color, opening) {
+        (Some(pos), _, _) => Some(Endpoint::At(pos)),
+        (None, Some(color), _) => Some(Endpoint::Color { color, tolerance }),
+        (None, None, Some(n)) => Some(Endpoint::NthOpening(n)),
+        (None, None, None) => None,
+    }
+}
+
+fn downscale_endpoint(endpoint: Endpoint, downscale: impl Fn(Position) -> Position) -> Endpoint {
+    match endpoint {
+        Endpoint::At(pos) => Endpoint::At(downscale(pos)),
+        other => other,
+    }
+}
+
+fn end_endpoints(
+    opt: &Opt,
+    levels: Option<&Levels>,
+    size: (u32, u32),
+) -> Result<Vec<Endpoint>, cli_errors::InvalidLevelError> {
+    let mut ends = Vec::new();
+    for &pos in &opt.end {
+        ends.push(Endpoint::At(stacked_position(pos, levels, size)?));
+    }
+    ends.extend(opt.end_opening.iter().map(|&n| Endpoint::NthOpening(n)));
+    if let Some(color) = opt.end_color {
+        ends.push(Endpoint::Color {
+            color,
+            tolerance: opt.tolerance,
+        });
+    }
+    if opt.all_exits {
+        ends.push(Endpoint::AllOpenings);
+    }
+    Ok(ends)
+}
+
+/// A pixel given on the command line, on floor `level` of a maze with
+/// levels or on the only floor of any other maze.
+#[derive(Debug, Clone, Copy)]
+struct LevelPosition {
+    level: usize,
+    pos: Position,
+}
+
+/// The position of `pos` in the image the maze is built from, which stacks
+/// the floors of a maze with `levels`. `size` is the size of that image.
+fn stacked_position(
+    pos: LevelPosition,
+    levels: Option<&Levels>,
+    size: (u32, u32),
+) -> Result<Position, cli_errors::InvalidLevelError> {
+    let (count, (width, height)) = match levels {
+        Some(levels) => (levels.count(), levels.dimensions()),
+        None => (1, size),
+    };
+    let LevelPosition { level, pos } = pos;
+    // positions past the image are reported when the maze is built
+    if level >= count || (levels.is_some() && pos.row() >= height) {
+        return Err(cli_errors::InvalidLevelError {
+            level,
+            pos,
+            levels: count,
+            size: (width, height),
+        });
+    }
+    Ok(match levels {
+        Some(levels) => levels.to_stacked(level, pos),
+        None => pos,
+    })
+}
+
+/// `x,y`, or `level:x,y`.
+fn parse_level_position(s: &str) -> Result<LevelPosition, cli_errors::InvalidPositionError> {
+    let err = || cli_errors::InvalidPositionError(s.to_owned());
+    let (level, pos) = match s.find(':') {
+        Some(colon) => (
+            s[..colon].trim().parse().map_err(|_| err())?,
+            &s[colon + 1..],
+        ),
+        None => (0, s),
+    };
+    let pos = parse_position(pos).map_err(|_| err())?;
+    Ok(LevelPosition { level, pos })
+}
+
+fn parse_position(s: &str) -> Result<Position, cli_errors::InvalidPositionError> {
+    let err = || cli_errors::InvalidPositionError(s.to_owned());
+    let mut parts = s.splitn(2, ',');
+    match (parts.next(), parts.next()) {
+        (Some(x), Some(y)) => Ok(Position::new(
+            x.trim().parse().map_err(|_| err())?,
+            y.trim().parse().map_err(|_| err())?,
+        )),
+        _ => Err(err()),
+    }
+}
+
+fn parse_terrain(s: &str) -> Result<(Rgb<u8>, u32), cli_errors::InvalidTerrainError> {
+    let err = || cli_errors::InvalidTerrainError(s.to_owned());
+    let mut parts = s.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(color), Some(cost)) => Ok((
+            parse_color(color).map_err(|_| err())?,
+            cost.trim().parse().map_err(|_| err())?,
+        )),
+        _ => Err(err()),
+    }
+}
+
+fn parse_key(s: &str) -> Result<(Rgb<u8>, Rgb<u8>), cli_errors::InvalidKeyError> {
+    let err = || cli_errors::InvalidKeyError(s.to_owned());
+    let mut parts = s.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(key), Some(door)) => Ok((
+            parse_color(key).map_err(|_| err())?,
+            parse_color(door).map_err(|_| err())?,
+        )),
+        _ => Err(err()),
+    }
+}
+
+fn parse_one_way(s: &str) -> Result<(Direction, Rgb<u8>), cli_errors::InvalidOneWayError> {
+    let err = || cli_errors::InvalidOneWayError(s.to_owned());
+    let mut parts = s.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(dir), Some(color)) => Ok((
+            match dir.trim().to_lowercase().as_str() {
+                "north" | "n" => Direction::North,
+                "east" | "e" => Direction::East,
+                "south" | "s" => Direction::South,
+                "west" | "w" => Direction::West,
+                _ => return Err(err()),
+            },
+            parse_color(color).map_err(|_| err())?,
+        )),
+        _ => Err(err()),
+    }
+}
+
+/// What `--cell-size` was given.
+#[derive(Debug, Clone, Copy)]
+enum CellSize {
+    Auto,
+    Pixels(u32),
+}
+
+fn parse_cell_size(s: &str) -> Result<CellSize, cli_errors::InvalidCellSizeError> {
+    match s.trim() {
+        "auto" => Ok(CellSize::Auto),
+        n => n
+            .parse()
+            .map(CellSize::Pixels)
+            .map_err(|_| cli_errors::InvalidCellSizeError(s.to_owned())),
+    }
 }
 
-fn draw_path(buf: &mut RgbImage, maze_path: &[Position]) {
-    let length = maze_path.len();
+fn parse_format(s: &str) -> Result<Format, cli_errors::InvalidFormatError> {
+    Format::from_name(s).ok_or_else(|| cli_errors::InvalidFormatError(s.to_owned()))
+}
 
-    for i in 0..length - 1 {
-        let a = maze_path[i];
-        let b = maze_path[i + 1];
+fn parse_color(s: &str) -> Result<Rgb<u8>, cli_errors::InvalidColorError> {
+    let hex = s.trim_start_matches('#');
+    let err = || cli_errors::InvalidColorError(s.to_owned());
+    if hex.len() != 6 || !hex.is_ascii() {
+        return Err(err());
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| err());
+    Ok(Rgb {
+        data: [channel(0)?, channel(2)?, channel(4)?],
+    })
+}
+
+fn get_algos<'a>(
+    registry: &'a mut Registry,
+    algo_strs: &[String],
+    params: &[String],
+) -> Result<Vec<&'a dyn Solver>, Box<dyn Error>> {
+    for algo_str in algo_strs {
+        if registry.get(algo_str).is_none() {
+            return Err(Box::new(cli_errors::InvalidAlgorithmError {
+                name: algo_str.to_owned(),
+                suggestion: registry.suggest(algo_str).map(str::to_owned),
+                valid: registry.names().into_iter().map(str::to_owned).collect(),
+            }));
+        }
+    }
 
-        // Blue - red
-        let r = ((i as f32 / length as f32) * 255.0) as u8;
-        let px = Rgb {
-            data: [r, 0, 255 - r],
+    for param in params {
+        let mut parts = param.splitn(2, '=');
+        let (name, value) = match (parts.next(), parts.next()) {
+            (Some(name), Some(value)) => (name, value),
+            _ => return Err(Box::new(cli_errors::InvalidParameterError(param.clone()))),
         };
 
-        if a.row() == b.row() {
-            for x in u32::min(a.col(), b.col())..=u32::max(a.col(), b.col()) {
-                buf[(x, a.row())] = px;
-            }
-        } else {
-            for y in u32::min(a.row(), b.row())..=u32::max(a.row(), b.row()) {
-                buf[(a.col(), y)] = px;
-            }
+        // set on every chosen algorithm that takes it, else let the first one report the error
+        let mut targets: Vec<&String> = algo_strs
+            .iter()
+            .filter(|a| {
+                let algo = registry.get(a).unwrap();
+                algo.parameters().iter().any(|p| p.name == name)
+            })
+            .collect();
+        if targets.is_empty() {
+            targets.push(&algo_strs[0]);
+        }
+        for algo_str in targets {
+            registry
+                .get_mut(algo_str)
+                .unwrap()
+                .set_parameter(name, value)?;
         }
     }
+
+    let registry = &*registry;
+    Ok(algo_strs.iter().map(|a| registry.get(a).unwrap()).collect())
 }
 
-type AlgoResult =
-    Result<fn(&Maze) -> (Option<Vec<Position>>, usize), cli_errors::InvalidAlgorithmError>;
-fn get_algo(algo_str: &str) -> AlgoResult {
-    match algo_str {
-        "bfs" => Ok(pathfinder::bfs),
-        "dijk" => Ok(pathfinder::dijk),
-        "dijk2" => Ok(pathfinder::dijk2),
-        "dfs" => Ok(pathfinder::dfs),
-        "astar" => Ok(pathfinder::astar),
-        _ => Err(cli_errors::InvalidAlgorithmError(algo_str.to_owned())),
+fn algo_help(registry: &Registry) -> String {
+    let mut help = String::from("ALGORITHMS:");
+    let width = registry
+        .iter()
+        .map(|a| a.name().len() + 2)
+        .fold(8, usize::max);
+    for algo in registry.iter() {
+        help.push_str(&format!(
+            "\n    {:<width$}{}",
+            algo.name(),
+            algo.description(),
+            width = width
+        ));
+        for param in algo.parameters() {
+            help.push_str(&format!(
+                "\n    {:width$}-p {}=<value>  {} (default: {})",
+                "",
+                param.name,
+                param.description,
+                param.default,
+                width = width
+            ));
+        }
     }
+    help
 }
 
 fn save_solved(
     img: &RgbImage,
-    img_path: &PathBuf,
-    out_path: &PathBuf,
-    algo_str: &str,
+    opt: &Opt,
+    format: Format,
+    naming: &Naming,
 ) -> Result<(), Box<dyn Error>> {
-    let name = img_path
-        .file_name()
-        .unwrap()
-        .to_str()
-        .expect("Could not convert filename to string");
-    let dot_index = if let Some(i) = name.find('.') {
-        i
-    } else {
-        name.len()
-    };
-    let mut name = name[..dot_index].to_owned();
-    name.push_str(&format!("{}{}{}", "-", algo_str, ".png"));
-    let name = OsString::from(name);
+    status!("Saving Image");
+    let (mut out, name) = solved_output(opt, naming, format.extension())?;
+    format.encode(img, &mut out)?;
+    out.flush()?;
+    status!("Saved to: {}", name);
+    Ok(())
+}
 
-    println!("Saving Image");
-    if !out_path.exists() {
-        fs::create_dir(&out_path)?;
+/// Saves what `draw` writes in `format`, see [`svg::write_svg`] and
+/// [`animate::write_gif`].
+fn save_drawing(
+    opt: &Opt,
+    format: Format,
+    naming: &Naming,
+    draw: impl FnOnce(&mut dyn Write) -> io::Result<()>,
+) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Gif => status!("Saving Animation"),
+        _ => status!("Saving Drawing"),
     }
-    let file = out_path.join(name);
-    img.save(&file)?;
-    let full_path = file.canonicalize()?;
-    println!("Saved to: {}", full_path.display());
+    let (mut out, name) = solved_output(opt, naming, format.extension())?;
+    draw(&mut out)?;
+    out.flush()?;
+    status!("Saved to: {}", name);
+    Ok(())
+}
+
+/// Copies the image at `img_path` with `pixels` painted over it one row at
+/// a time, see [`render::stream_png`].
+fn save_streamed(
+    pixels: &[(Position, Rgb<u8>)],
+    img_path: &Path,
+    opt: &Opt,
+    naming: &Naming,
+) -> Result<(), Box<dyn Error>> {
+    status!("Saving Image");
+    let (out, name) = solved_output(opt, naming, "png")?;
+    let mut rows = row_source(img_path)?;
+    render::stream_png(rows.as_mut(), out, pixels)?;
+    status!("Saved to: {}", name);
+    Ok(())
+}
+
+fn save_solved_text(text: &str, opt: &Opt, naming: &Naming) -> Result<(), Box<dyn Error>> {
+    status!("Saving Text");
+    let (mut out, name) = solved_output(opt, naming, "txt")?;
+    out.write_all(text.as_bytes())?;
+    out.flush()?;
+    status!("Saved to: {}", name);
     Ok(())
 }
 
+/// Where to write a solution, and its name to report: `--output-file` if it
+/// is given, stdout if the output is `-`, otherwise [`solved_file`].
+fn solved_output(
+    opt: &Opt,
+    naming: &Naming,
+    ext: &str,
+) -> Result<(Box<dyn Write>, String), Box<dyn Error>> {
+    let file = match &opt.output_file {
+        Some(file) => file.clone(),
+        None if is_stdio(&opt.output) => {
+            return Ok((Box::new(BufWriter::new(io::stdout())), "stdout".to_owned()));
+        }
+        None => solved_file(&opt.output, &opt.output_name, naming, ext)?,
+    };
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let out = BufWriter::new(fs::File::create(&file)?);
+    let full_path = file.canonicalize()?;
+    Ok((Box::new(out), full_path.display().to_string()))
+}
+
+/// Whether `path` is `-`, standing for stdin or stdout.
+fn is_stdio(path: &Path) -> bool {
+    path == Path::new("-")
+}
+
+/// Path in `out_path` named by filling in `template`.
+fn solved_file(
+    out_path: &Path,
+    template: &str,
+    naming: &Naming,
+    ext: &str,
+) -> Result<PathBuf, Box<dyn Error>> {
+    let mut name = OsString::from(output::expand(template, naming)?);
+    name.push(".");
+    name.push(ext);
+    Ok(out_path.join(name))
+}
+
+/// The file name of `path` without its extension, `stdin` for `-`.
+fn file_stem(path: &Path) -> &str {
+    if is_stdio(path) {
+        return "stdin";
+    }
+    path.file_stem()
+        .unwrap()
+        .to_str()
+        .expect("Could not convert filename to string")
+}
+
+/// The `--format` given, or the one of the `--output-file` extension, or png.
+fn image_format(opt: &Opt) -> Format {
+    chosen_format(opt).unwrap_or(Format::Png)
+}
+
+/// The `--format` given, or the one of the `--output-file` extension.
+fn chosen_format(opt: &Opt) -> Option<Format> {
+    let from_file = opt
+        .output_file
+        .as_ref()
+        .and_then(|f| f.extension())
+        .and_then(|e| e.to_str())
+        .and_then(Format::from_name);
+    opt.format.or(from_file)
+}
+
 fn colortype_to_str(ct: image::ColorType) -> (&'static str, u8) {
     use image::ColorType::*;
     match ct {
@@ -226,7 +1258,7 @@ where
     } else {
         point_index = s.len();
     }
-    let b = s[start_index..point_index].as_bytes();
+    let b = &s.as_bytes()[start_index..point_index];
 
     let chunks = b.rchunks_exact(3);
     let mut s_chunks: Vec<_> = Vec::new();
@@ -240,7 +1272,7 @@ where
     }
 
     // if length perfectly divisible by 3, the fist index will be empty.
-    let mut out = if s_chunks[0] != "" {
+    let mut out = if !s_chunks[0].is_empty() {
         s_chunks.join(",")
     } else {
         s_chunks[1..].join(",")
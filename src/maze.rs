@@ -1,191 +1,790 @@
-#![allow(unused)]
+mod build;
+pub mod classify;
 pub mod errors;
+pub mod keys;
+pub mod levels;
 pub mod node;
+pub mod oneway;
+pub mod portal;
+pub mod scale;
+pub mod stream;
+pub mod terrain;
+pub mod text;
+mod validate;
 
-use image::GenericImageView;
+use std::collections::{HashMap, HashSet};
+use std::iter;
 
-use std::cell::RefCell;
-use std::collections::HashMap;
-use std::rc::Rc;
+use image::Rgb;
 
+use classify::{matches_color, WallClassifier};
 use errors::MazeError;
-use node::Node;
+use keys::Keys;
+use node::{Node, NodeIndex, Position};
+use oneway::OneWay;
+use portal::Portals;
+use stream::RowSource;
+use terrain::Terrain;
 
+pub use validate::validate;
+
+/// A maze graph built from an image.
+///
+/// Only the interesting pixels (junctions, corners and dead ends) become
+/// nodes; straight corridors between them are collapsed into single links.
+/// Nodes live in one arena and refer to each other by [`NodeIndex`], so
+/// dropping the maze frees the whole graph. The graph cannot be changed once
+/// built and a `Maze` is `Send + Sync`, so one maze can be searched from
+/// several threads at once.
 #[derive(Debug, Clone)]
 pub struct Maze {
     width: u32,
     height: u32,
-    count: usize,
-    start: Rc<RefCell<Node>>,
-    end: Rc<RefCell<Node>>,
+    nodes: Vec<Node>,
+    openings: Vec<Position>,
+    start: NodeIndex,
+    ends: Vec<NodeIndex>,
+    links: Vec<[NodeIndex; 2]>,
+    connectivity: Connectivity,
+    keys: HashMap<NodeIndex, usize>,
+    doors: HashMap<NodeIndex, usize>,
+}
+
+/// Settings for turning an image into a [`Maze`].
+#[derive(Debug, Clone)]
+pub struct MazeOptions {
+    /// Decides which pixels are walls.
+    pub walls: WallClassifier,
+    /// Where the search starts.
+    pub start: Endpoint,
+    /// Where the search may end. Reaching any one of them solves the maze.
+    pub ends: Vec<Endpoint>,
+    /// What each open pixel costs to cross.
+    pub terrain: Terrain,
+    /// Pairs of pixels linked to each other.
+    pub portals: Portals,
+    /// Pixels that can only be crossed in one direction.
+    pub one_way: OneWay,
+    /// Doors that only open once their key is picked up.
+    pub keys: Keys,
+    /// Other links between pixels that are not next to each other, such as
+    /// stairs between floors.
+    pub links: Vec<Link>,
+    /// Which neighboring pixels a step may move to.
+    pub connectivity: Connectivity,
+    /// Height of one floor when the image stacks several, one row of wall
+    /// apart as [`Levels`](levels::Levels) does. Each floor then has a
+    /// border of its own to find openings in.
+    pub level_height: Option<u32>,
+}
+
+/// Which neighboring pixels a step may move to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Connectivity {
+    /// Up, down, left and right only. Straight corridors collapse into single
+    /// links.
+    #[default]
+    Four,
+    /// Diagonal steps too, costing √2 times a straight step. Every open pixel
+    /// becomes a node. Without `corner_cutting` a diagonal step needs both
+    /// pixels beside it to be open.
+    Eight { corner_cutting: bool },
+}
+
+// methods
+impl Connectivity {
+    /// Cost of one straight and one diagonal step on terrain costing 1. On
+    /// 8-connected mazes costs are in fixed point, with √2 rounded to
+    /// `14_142 / 10_000`. On 4-connected mazes a diagonal counts as two
+    /// straight steps.
+    pub fn step_costs(self) -> (u32, u32) {
+        match self {
+            Connectivity::Four => (1, 2),
+            Connectivity::Eight { .. } => (10_000, 14_142),
+        }
+    }
+}
+
+/// A link between two open pixels, usable in both directions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Link {
+    pub a: Position,
+    pub b: Position,
+    /// Cost of moving along the link.
+    pub cost: u32,
+}
+
+impl Default for MazeOptions {
+    fn default() -> MazeOptions {
+        MazeOptions {
+            walls: WallClassifier::default(),
+            start: Endpoint::Opening,
+            ends: vec![Endpoint::Opening],
+            terrain: Terrain::default(),
+            portals: Portals::default(),
+            one_way: OneWay::default(),
+            keys: Keys::default(),
+            links: Vec::new(),
+            connectivity: Connectivity::default(),
+            level_height: None,
+        }
+    }
+}
+
+/// How to find the start or the exit of a maze.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endpoint {
+    /// The first opening in the border that no other end uses. Openings are
+    /// ordered as described in [`openings`].
+    #[default]
+    Opening,
+    /// Every opening in the border that no other end uses. As a start only
+    /// the first of them is taken.
+    AllOpenings,
+    /// The opening at this index in [`openings`].
+    NthOpening(usize),
+    /// The given pixel, which must not be a wall.
+    At(Position),
+    /// The first pixel, scanning rows top to bottom, within `tolerance` of
+    /// `color` on every channel. Pixels of this color are never walls.
+    Color { color: Rgb<u8>, tolerance: u8 },
+}
+
+impl Endpoint {
+    /// Whether `px` is a marker pixel for this endpoint.
+    fn is_marker(&self, px: Rgb<u8>) -> bool {
+        match *self {
+            Endpoint::Color { color, tolerance } => classify::matches_color(px, color, tolerance),
+            _ => false,
+        }
+    }
+
+    /// Whether the endpoint is found by its color.
+    fn is_marker_endpoint(&self) -> bool {
+        matches!(self, Endpoint::Color { .. })
+    }
+
+    /// Order in which endpoints are placed: fixed positions first, so that
+    /// openings are picked around them.
+    fn rank(&self) -> u8 {
+        match self {
+            Endpoint::Opening => 1,
+            Endpoint::AllOpenings => 2,
+            _ => 0,
+        }
+    }
 }
 
+// Solvers running on other threads borrow the maze.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Maze>();
+};
+
 // associate functions
 impl Maze {
-    pub fn new(img: &image::RgbImage) -> Result<Maze, MazeError> {
-        const WALL: image::Rgb<u8> = image::Rgb { data: [0, 0, 0] };
-
-        let width = img.width();
-        let height = img.height();
-        let buf = img;
-
-        let mut start: Option<Rc<RefCell<Node>>> = None;
-        let mut top_nodes: HashMap<u32, Rc<RefCell<Node>>> = HashMap::new();
-        let mut count = 0;
-
-        // find start
-        for x in 1..width - 1 {
-            if buf[(x, 0)] != WALL {
-                let new = Rc::new(RefCell::new(Node::new(x, 0)));
-                top_nodes.insert(x, Rc::clone(&new));
-                start = Some(new);
-                count += 1;
-                break;
-            }
+    /// Builds the graph for a maze drawn as text, see [`text`] for the format.
+    pub fn from_text(text: &str) -> Result<Maze, MazeError> {
+        text::TextMaze::parse(text)?.to_maze()
+    }
+
+    /// Builds the graph for a whole file read into `bytes`: an image in any
+    /// format the `image` crate can guess from its bytes, or else a text
+    /// maze. Images are read as by [`new`](Maze::new).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Maze, MazeError> {
+        let read_error = |e: &dyn std::error::Error| MazeError::ReadError(e.to_string());
+        if image::guess_format(bytes).is_err() {
+            let text = std::str::from_utf8(bytes).map_err(|e| read_error(&e))?;
+            return Maze::from_text(text);
         }
+        let img = image::load_from_memory(bytes).map_err(|e| read_error(&e))?;
+        Maze::new(&img.to_rgb())
+    }
 
-        let start = if let Some(s) = start {
-            s
-        } else {
-            return Err(MazeError::NoStartError);
-        };
+    /// Builds the graph for `img`. Black pixels are walls and every other
+    /// pixel is a path. The start and the exit are the first two openings in
+    /// the border, see [`openings`]. See [`MazeOptions`] for other kinds of
+    /// image.
+    pub fn new(img: &image::RgbImage) -> Result<Maze, MazeError> {
+        Maze::with_options(img, &MazeOptions::default())
+    }
 
-        for y in 1..height - 1 {
-            // track if adjacent horizontal pixels are a path
-            let mut prv: bool;
-            let mut cur = false;
-            let mut nxt = buf[(1, y)] != WALL;
+    /// Builds the graph for `img` using `options` instead of the defaults. A
+    /// node is placed at the start, at every exit, on every key and door and
+    /// at both ends of every link even if they sit in the middle of a
+    /// corridor. The costs of links are given in straight steps and scaled
+    /// by [`step_costs`](Connectivity::step_costs).
+    pub fn with_options(img: &image::RgbImage, options: &MazeOptions) -> Result<Maze, MazeError> {
+        Maze::from_rows(&mut stream::ImageRows::new(img), options)
+    }
 
-            let mut left_node: Option<Rc<RefCell<Node>>> = None;
+    /// [`with_options`](Maze::with_options) for an image read one row at a
+    /// time, see [`stream`]. Only a few rows are held at once, so memory
+    /// grows with the graph rather than with the image. Every opening in the
+    /// border and every pixel of a marker or portal color becomes a node,
+    /// since the endpoints are only placed once all rows have been read.
+    pub fn from_rows(source: &mut dyn RowSource, options: &MazeOptions) -> Result<Maze, MazeError> {
+        let (width, height) = source.dimensions();
+        if options.keys.pairs.len() > Keys::MAX {
+            return Err(MazeError::TooManyKeysError(options.keys.pairs.len()));
+        }
 
-            for x in 1..width - 1 {
-                prv = cur;
-                cur = nxt;
-                nxt = buf[(x + 1, y)] != WALL;
+        // pixels known to need a node before reading them, in row order
+        let endpoints: Vec<Endpoint> = Some(options.start)
+            .into_iter()
+            .chain(options.ends.iter().copied())
+            .collect();
+        let mut fixed: Vec<Position> = endpoints
+            .iter()
+            .filter_map(|e| match *e {
+                Endpoint::At(pos) => Some(pos),
+                _ => None,
+            })
+            .chain(
+                options
+                    .links
+                    .iter()
+                    .flat_map(|l| iter::once(l.a).chain(iter::once(l.b))),
+            )
+            .collect();
+        fixed.sort_unstable_by_key(|p| (p.row(), p.col()));
+        fixed.dedup();
+        let markers: Vec<Endpoint> = endpoints
+            .iter()
+            .copied()
+            .filter(|e| e.is_marker_endpoint())
+            .collect();
 
-                let mut n: Option<Rc<RefCell<Node>>> = None;
+        let last_col = width.saturating_sub(1);
+        let mut sides: [Vec<Position>; 4] = Default::default();
+        let mut side_open = [false; 2];
+        let mut opening_set = HashSet::new();
+        let mut walled = Vec::new();
+        let mut found_markers: Vec<Option<Position>> = vec![None; markers.len()];
+        let portals = &options.portals;
+        let mut portal_pixels: Vec<Vec<Position>> = vec![Vec::new(); portals.colors.len()];
 
-                if !cur {
-                    // on wall
-                    continue;
+        let mut builder = build::RowBuilder::new(options, width);
+        let mut row = vec![0; width as usize * 3];
+        for y in 0..height {
+            source.read_row(&mut row)?;
+            let px = |x: u32| {
+                let i = x as usize * 3;
+                Rgb {
+                    data: [row[i], row[i + 1], row[i + 2]],
                 }
+            };
+            let open = |x: u32| passable(options, px(x));
 
-                if prv {
-                    // PATH PATH PATH
-                    if nxt {
-                        // Create node if paths above or below
-                        if buf[(x, y - 1)] != WALL || buf[(x, y + 1)] != WALL {
-                            let new = Rc::new(RefCell::new(Node::new(x, y)));
-                            if let Some(node) = &left_node {
-                                node.borrow_mut().set_east(Rc::clone(&new));
-                                new.borrow_mut().set_west(Rc::clone(&node));
-                            }
-                            left_node = Some(Rc::clone(&new));
-                            n = Some(new);
-                        }
-                    // PATH PATH WALL
-                    // create path at end of corridor
-                    } else {
-                        let new = Rc::new(RefCell::new(Node::new(x, y)));
-                        if let Some(node) = &left_node {
-                            node.borrow_mut().set_east(Rc::clone(&new));
-                            new.borrow_mut().set_west(Rc::clone(&node));
-                        }
-                        left_node = None;
-                        n = Some(new);
+            // openings, as in `Grid::openings`; corners belong to the top
+            // and bottom rows
+            let mut runs = |side: usize, xs: &mut dyn Iterator<Item = u32>, prv: &mut bool| {
+                for x in xs {
+                    let cur = open(x);
+                    if cur && !*prv {
+                        sides[side].push(Position::new(x, y));
+                        opening_set.insert(Position::new(x, y));
                     }
-                } else {
-                    // WALL PATH PATH
-                    // create path at start of corridor
-                    if nxt {
-                        let new = Rc::new(RefCell::new(Node::new(x, y)));
-                        left_node = Some(Rc::clone(&new));
-                        n = Some(new);
-
-                    // WALL PATH WALL
-                    } else {
-                        // create node only if dead end
-                        if buf[(x, y - 1)] == WALL || buf[(x, y + 1)] == WALL {
-                            n = Some(Rc::new(RefCell::new(Node::new(x, y))));
+                    *prv = cur;
+                }
+            };
+            if width > 0 {
+                let floor_row = floor_row(options, height, y);
+                match floor_row {
+                    FloorRow::Top => runs(0, &mut (0..width), &mut false),
+                    FloorRow::Middle => {
+                        let [left, right] = &mut side_open;
+                        runs(1, &mut iter::once(0), left);
+                        if last_col > 0 {
+                            runs(2, &mut iter::once(last_col), right);
                         }
                     }
+                    FloorRow::Bottom => runs(3, &mut (0..width), &mut false),
+                    FloorRow::Between => {}
                 }
+                if floor_row != FloorRow::Middle {
+                    side_open = [false; 2];
+                }
+            }
 
-                // If node isn't none, we can try to connect it
-                if let Some(node) = n {
-                    // clear above, connect to top node
-                    if buf[(x, y - 1)] != WALL {
-                        let top = top_nodes.get(&x);
-                        if let Some(tn) = top {
-                            tn.borrow_mut().set_south(Rc::clone(&node));
-                            node.borrow_mut().set_north(Rc::clone(&tn));
+            let in_row = fixed.iter().filter(|p| p.row() == y && p.col() < width);
+            walled.extend(in_row.filter(|p| !open(p.col())));
+            for (found, marker) in found_markers.iter_mut().zip(&markers) {
+                if found.is_none() {
+                    *found = (0..width)
+                        .find(|&x| marker.is_marker(px(x)))
+                        .map(|x| Position::new(x, y));
+                }
+            }
+            if !portals.colors.is_empty() {
+                for x in 0..width {
+                    for (i, &c) in portals.colors.iter().enumerate() {
+                        if matches_color(px(x), c, portals.tolerance) {
+                            portal_pixels[i].push(Position::new(x, y));
                         }
                     }
+                }
+            }
 
-                    // if clear below, put this node in the top row for next connection
-                    if buf[(x, y + 1)] != WALL {
-                        top_nodes.insert(x, Rc::clone(&node));
-                    } else {
-                        top_nodes.remove(&x);
-                    }
+            builder.push_row(&row, &|pos, px| {
+                forced(options, &fixed, &opening_set, &markers, pos, px)
+            });
+        }
+        let graph =
+            builder.finish(&|pos, px| forced(options, &fixed, &opening_set, &markers, pos, px));
+        let openings = sides.concat();
 
-                    count += 1;
+        let placed = place_endpoints(options, &openings, |e| {
+            markers
+                .iter()
+                .position(|&m| m == e)
+                .and_then(|i| found_markers[i])
+        });
+        let start = *placed[0].first().ok_or(MazeError::NoStartError)?;
+        let mut ends = Vec::new();
+        for found in &placed[1..] {
+            if found.is_empty() {
+                return Err(MazeError::NoExitError);
+            }
+            for &pos in found {
+                if !ends.contains(&pos) {
+                    ends.push(pos);
                 }
             }
         }
+        if ends.is_empty() {
+            return Err(MazeError::NoExitError);
+        }
+        let check = |pos: Position| {
+            if pos.col() >= width || pos.row() >= height {
+                Err(MazeError::OutOfBoundsError(pos))
+            } else if walled.contains(&pos) {
+                Err(MazeError::WallEndpointError(pos))
+            } else {
+                Ok(())
+            }
+        };
+        for &pos in Some(&start).into_iter().chain(&ends) {
+            check(pos)?;
+        }
 
-        // last row
-        let mut end: Option<Rc<RefCell<Node>>> = None;
-        let y = height - 1;
-        for x in 1..width - 1 {
-            if buf[(x, y)] != WALL {
-                let new = Rc::new(RefCell::new(Node::new(x, y)));
-                let top = top_nodes.get(&x);
-                if let Some(tn) = top {
-                    tn.borrow_mut().set_south(Rc::clone(&new));
-                    new.borrow_mut().set_north(Rc::clone(tn));
-                }
-                end = Some(new);
-                count += 1;
-                break;
+        let mut links = Vec::new();
+        for (&color, found) in portals.colors.iter().zip(portal_pixels) {
+            match found[..] {
+                [a, b] => links.push(Link {
+                    a,
+                    b,
+                    cost: portals.cost,
+                }),
+                _ => return Err(MazeError::PortalPairError(color, found)),
             }
         }
+        for l in &options.links {
+            check(l.a)?;
+            check(l.b)?;
+        }
+        links.extend_from_slice(&options.links);
 
-        let end = if let Some(node) = end {
-            node
-        } else {
-            return Err(MazeError::NoExitError);
+        let build::Graph {
+            mut nodes,
+            keys,
+            doors,
+        } = graph;
+
+        // nodes are created in row order, so they can be searched by position
+        let find = |pos: Position| {
+            nodes
+                .binary_search_by_key(&(pos.row(), pos.col()), |n| {
+                    (n.position().row(), n.position().col())
+                })
+                .ok()
+                .map(|i| i as NodeIndex)
         };
+        let start = find(start).ok_or(MazeError::NoStartError)?;
+        let ends = ends
+            .into_iter()
+            .map(|pos| find(pos).ok_or(MazeError::NoExitError))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let (straight, _) = options.connectivity.step_costs();
+        let linked: Vec<([NodeIndex; 2], u32)> = links
+            .iter()
+            .filter_map(|l| Some(([find(l.a)?, find(l.b)?], l.cost.saturating_mul(straight))))
+            .collect();
+        for &([a, b], cost) in &linked {
+            nodes[a as usize].add_link(b, cost);
+            nodes[b as usize].add_link(a, cost);
+        }
 
         Ok(Maze {
             width,
             height,
-            count,
+            nodes,
+            openings,
             start,
-            end,
+            ends,
+            links: linked.into_iter().map(|(pair, _)| pair).collect(),
+            connectivity: options.connectivity,
+            keys,
+            doors,
         })
     }
 }
 
 // methods
 impl Maze {
+    /// Width of the source image in pixels.
     pub fn width(&self) -> u32 {
         self.width
     }
 
+    /// Height of the source image in pixels.
     pub fn height(&self) -> u32 {
         self.height
     }
 
+    /// Number of nodes in the graph.
     pub fn count(&self) -> usize {
-        self.count
+        self.nodes.len()
+    }
+
+    /// The node the search starts from.
+    pub fn start(&self) -> &Node {
+        &self.nodes[self.start as usize]
+    }
+
+    /// The first of the exits, see [`ends`](Maze::ends).
+    pub fn end(&self) -> &Node {
+        &self.nodes[self.ends[0] as usize]
+    }
+
+    pub fn start_index(&self) -> NodeIndex {
+        self.start
+    }
+
+    pub fn end_index(&self) -> NodeIndex {
+        self.ends[0]
+    }
+
+    /// Every exit, in the order they were given. There is always at least
+    /// one.
+    pub fn ends(&self) -> &[NodeIndex] {
+        &self.ends
+    }
+
+    /// Which neighboring pixels a step may move to.
+    pub fn connectivity(&self) -> Connectivity {
+        self.connectivity
+    }
+
+    /// Cost of one straight and one diagonal step, see
+    /// [`Connectivity::step_costs`].
+    pub fn step_costs(&self) -> (u32, u32) {
+        self.connectivity.step_costs()
+    }
+
+    /// Pairs of nodes joined by a link other than a corridor, from portals
+    /// and [`MazeOptions::links`].
+    pub fn links(&self) -> &[[NodeIndex; 2]] {
+        &self.links
+    }
+
+    /// The pair whose key lies on the node at `index`, as an index into
+    /// [`Keys::pairs`].
+    pub fn key_at(&self, index: NodeIndex) -> Option<usize> {
+        if self.keys.is_empty() {
+            return None;
+        }
+        self.keys.get(&index).copied()
+    }
+
+    /// The pair whose door lies on the node at `index`, as an index into
+    /// [`Keys::pairs`].
+    pub fn door_at(&self, index: NodeIndex) -> Option<usize> {
+        if self.doors.is_empty() {
+            return None;
+        }
+        self.doors.get(&index).copied()
+    }
+
+    /// Whether the maze has any doors, which only
+    /// [`keys_bfs`](crate::pathfinder::keys_bfs) and
+    /// [`keys_astar`](crate::pathfinder::keys_astar) take into account.
+    pub fn has_doors(&self) -> bool {
+        !self.doors.is_empty()
+    }
+
+    /// Whether the node at `index` is one of the exits.
+    pub fn is_end(&self, index: NodeIndex) -> bool {
+        self.ends.contains(&index)
+    }
+
+    /// The node stored at `index`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` does not belong to this maze.
+    pub fn node(&self, index: NodeIndex) -> &Node {
+        &self.nodes[index as usize]
+    }
+
+    /// Every node, ordered by [`NodeIndex`].
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    /// The openings in the border of the image, see [`openings`].
+    pub fn openings(&self) -> &[Position] {
+        &self.openings
+    }
+}
+
+/// Finds the gaps in the border of `img`, which are the usual entrances and
+/// exits of a maze. Each run of open pixels along one side counts as one
+/// opening and is reported by its first pixel. Openings are listed side by
+/// side: the top row left to right, then the left and right columns top to
+/// bottom, then the bottom row left to right. With
+/// [`level_height`](MazeOptions::level_height) set, each side lists the
+/// border of every floor in turn, top floor first.
+pub fn openings(img: &image::RgbImage, options: &MazeOptions) -> Vec<Position> {
+    Grid { img, options }.openings()
+}
+
+/// Where a row of the image lies on its floor, see
+/// [`MazeOptions::level_height`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FloorRow {
+    Top,
+    Middle,
+    Bottom,
+    /// The row of wall between two floors.
+    Between,
+}
+
+/// Where row `y` of an image `height` pixels high lies on its floor. The
+/// top row of a one pixel high floor is not its bottom row too.
+fn floor_row(options: &MazeOptions, height: u32, y: u32) -> FloorRow {
+    let (row, floor) = match options.level_height {
+        Some(h) if h > 0 => (y % (h + 1), h),
+        _ => (y, height),
+    };
+    if row == 0 {
+        FloorRow::Top
+    } else if row >= floor {
+        FloorRow::Between
+    } else if row + 1 == floor {
+        FloorRow::Bottom
+    } else {
+        FloorRow::Middle
+    }
+}
+
+/// An image seen through a set of options.
+struct Grid<'a> {
+    img: &'a image::RgbImage,
+    options: &'a MazeOptions,
+}
+
+impl<'a> Grid<'a> {
+    /// Whether the pixel at `x`, `y` can be walked on. Pixels outside the
+    /// image count as walls.
+    fn open(&self, x: u32, y: u32) -> bool {
+        x < self.img.width() && y < self.img.height() && passable(self.options, self.img[(x, y)])
+    }
+
+    /// See [`place_endpoints`].
+    fn place_endpoints(&self, openings: &[Position]) -> Vec<Vec<Position>> {
+        let (width, height) = self.img.dimensions();
+        place_endpoints(self.options, openings, |endpoint| {
+            (0..height)
+                .flat_map(|y| (0..width).map(move |x| Position::new(x, y)))
+                .find(|p| endpoint.is_marker(self.img[p.as_tuple()]))
+        })
+    }
+
+    /// Whether a step from the open pixel `a` to `b` may be taken. Steps onto
+    /// or off a one-way pixel must go its way.
+    fn can_step(&self, a: Position, b: Position) -> bool {
+        let (img, one_way) = (self.img, &self.options.one_way);
+        one_way.allows(a, img[a.as_tuple()], b, img[b.as_tuple()])
+    }
+
+    fn openings(&self) -> Vec<Position> {
+        let (width, height) = self.img.dimensions();
+        if width == 0 || height == 0 {
+            return Vec::new();
+        }
+        let last_col = width - 1;
+
+        // each side is a list of runs of pixels along the border of a floor;
+        // corners belong to the top and bottom rows
+        let mut sides: [Vec<Vec<Position>>; 4] = Default::default();
+        let row = |y: u32| (0..width).map(|x| Position::new(x, y)).collect();
+        let mut prv_row = FloorRow::Between;
+        for y in 0..height {
+            let floor_row = floor_row(self.options, height, y);
+            match floor_row {
+                FloorRow::Top => sides[0].push(row(y)),
+                FloorRow::Middle => {
+                    if prv_row != FloorRow::Middle {
+                        sides[1].push(Vec::new());
+                        sides[2].push(Vec::new());
+                    }
+                    sides[1].last_mut().unwrap().push(Position::new(0, y));
+                    // a one pixel wide image has the same left and right
+                    // column
+                    if last_col > 0 {
+                        sides[2]
+                            .last_mut()
+                            .unwrap()
+                            .push(Position::new(last_col, y));
+                    }
+                }
+                FloorRow::Bottom => sides[3].push(row(y)),
+                FloorRow::Between => {}
+            }
+            prv_row = floor_row;
+        }
+
+        let mut openings = Vec::new();
+        for run in sides.iter().flatten() {
+            let mut prv = false;
+            for &pos in run {
+                let cur = self.open(pos.col(), pos.row());
+                if cur && !prv {
+                    openings.push(pos);
+                }
+                prv = cur;
+            }
+        }
+        openings
     }
+}
+
+/// Whether `px` can be walked on: start and exit markers, portals, one-way
+/// pixels, keys and doors, and everything that is not a wall.
+fn passable(options: &MazeOptions, px: Rgb<u8>) -> bool {
+    options.start.is_marker(px)
+        || options.ends.iter().any(|e| e.is_marker(px))
+        || options.portals.is_portal(px)
+        || options.one_way.is_one_way(px)
+        || options.keys.is_key_or_door(px)
+        || !options.walls.is_wall(px)
+}
+
+/// Where the start and each exit of `options` lie, in that order. `marker`
+/// finds the pixel of a [`Color`] endpoint. An endpoint that cannot be found
+/// is left empty and only [`AllOpenings`] can give more than one position.
+///
+/// [`AllOpenings`]: Endpoint::AllOpenings
+/// [`Color`]: Endpoint::Color
+fn place_endpoints(
+    options: &MazeOptions,
+    openings: &[Position],
+    marker: impl Fn(Endpoint) -> Option<Position>,
+) -> Vec<Vec<Position>> {
+    let find_endpoint = |endpoint: Endpoint, taken: &[Position]| -> Vec<Position> {
+        match endpoint {
+            Endpoint::Opening => openings
+                .iter()
+                .copied()
+                .find(|p| !taken.contains(p))
+                .into_iter()
+                .collect(),
+            Endpoint::AllOpenings => openings
+                .iter()
+                .copied()
+                .filter(|p| !taken.contains(p))
+                .collect(),
+            Endpoint::NthOpening(n) => openings.get(n).copied().into_iter().collect(),
+            Endpoint::At(pos) => vec![pos],
+            Endpoint::Color { .. } => marker(endpoint).into_iter().collect(),
+        }
+    };
+
+    let endpoints: Vec<Endpoint> = Some(options.start)
+        .into_iter()
+        .chain(options.ends.iter().copied())
+        .collect();
+    let mut placed: Vec<Vec<Position>> = vec![Vec::new(); endpoints.len()];
+    let mut taken: Vec<Position> = Vec::new();
+    for rank in 0..3 {
+        for (i, endpoint) in endpoints.iter().enumerate() {
+            if endpoint.rank() == rank {
+                let mut found = find_endpoint(*endpoint, &taken);
+                if i == 0 {
+                    found.truncate(1);
+                }
+                taken.extend_from_slice(&found);
+                placed[i] = found;
+            }
+        }
+    }
+    placed
+}
+
+/// Whether the open pixel `px` at `pos` must become a node while reading
+/// rows for [`Maze::from_rows`]: it is a `fixed` position, an opening, or of
+/// a portal or `markers` color.
+fn forced(
+    options: &MazeOptions,
+    fixed: &[Position],
+    openings: &HashSet<Position>,
+    markers: &[Endpoint],
+    pos: Position,
+    px: Rgb<u8>,
+) -> bool {
+    (!fixed.is_empty()
+        && fixed
+            .binary_search_by_key(&(pos.row(), pos.col()), |p| (p.row(), p.col()))
+            .is_ok())
+        || openings.contains(&pos)
+        || options.portals.is_portal(px)
+        || markers.iter().any(|m| m.is_marker(px))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::pnm::{PNMSubtype, SampleEncoding};
+    use image::{DynamicImage, ImageOutputFormat};
 
-    pub fn start(&self) -> Rc<RefCell<Node>> {
-        Rc::clone(&self.start)
+    use crate::pathfinder;
+
+    fn encode(img: &image::RgbImage, format: ImageOutputFormat) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgb8(img.clone())
+            .write_to(&mut bytes, format)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn from_bytes_reads_every_lossless_format() {
+        let img = image::open("img/tiny.png").unwrap().to_rgb();
+        let expected = Maze::new(&img).unwrap();
+        let cost = pathfinder::dijk(&expected).cost;
+        let formats = vec![
+            ImageOutputFormat::PNG,
+            ImageOutputFormat::BMP,
+            ImageOutputFormat::GIF,
+            ImageOutputFormat::PNM(PNMSubtype::Pixmap(SampleEncoding::Binary)),
+        ];
+        for format in formats {
+            let name = format!("{:?}", format);
+            let maze = Maze::from_bytes(&encode(&img, format)).unwrap();
+            assert_eq!(maze.count(), expected.count(), "{}", name);
+            assert_eq!(maze.start().position(), expected.start().position());
+            assert_eq!(pathfinder::dijk(&maze).cost, cost, "{}", name);
+        }
     }
 
-    pub fn end(&self) -> Rc<RefCell<Node>> {
-        Rc::clone(&self.end)
+    #[test]
+    fn from_bytes_reads_anything_else_as_text() {
+        let text = "##S##\n#   #\n##E##\n";
+        let maze = Maze::from_bytes(text.as_bytes()).unwrap();
+        assert_eq!(maze.count(), Maze::from_text(text).unwrap().count());
+        assert_eq!(pathfinder::bfs(&maze).cost, 2);
+        assert!(Maze::from_bytes(&[0xff, 0xfe, 0x00]).is_err());
     }
 }
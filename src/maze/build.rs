@@ -0,0 +1,485 @@
+//! Building the graph one row of pixels at a time.
+//!
+//! Only the rows above and below the one being built are kept, along with the
+//! nodes found so far and one pending corridor per column, so memory grows
+//! with the graph rather than with the image.
+
+use std::collections::HashMap;
+
+use image::Rgb;
+
+use super::node::{Node, NodeIndex, Position};
+use super::{passable, Connectivity, MazeOptions};
+
+/// Nodes and the keys and doors on them, as stored in a
+/// [`Maze`](super::Maze).
+pub(super) struct Graph {
+    pub nodes: Vec<Node>,
+    pub keys: HashMap<NodeIndex, usize>,
+    pub doors: HashMap<NodeIndex, usize>,
+}
+
+/// A corridor walked from `node` so far, with the cost of walking it away
+/// from the node and back.
+#[derive(Debug, Clone, Copy)]
+struct Corridor {
+    node: NodeIndex,
+    px: Rgb<u8>,
+    forward: u32,
+    back: u32,
+}
+
+impl Corridor {
+    fn new(node: NodeIndex, px: Rgb<u8>) -> Corridor {
+        Corridor {
+            node,
+            px,
+            forward: 0,
+            back: 0,
+        }
+    }
+
+    /// Walks on to a pixel costing `cost` from one costing `last`.
+    fn step(&mut self, cost: u32, last: u32) {
+        self.forward = self.forward.saturating_add(cost);
+        self.back = self.back.saturating_add(last);
+    }
+}
+
+/// Setter of a neighbor of a [`Node`], such as [`Node::set_east`].
+type Setter = fn(&mut Node, NodeIndex, u32);
+
+pub(super) struct RowBuilder<'a> {
+    options: &'a MazeOptions,
+    width: u32,
+    // the rows above, at and below the one being built, empty outside the
+    // image
+    pixels: [Vec<Rgb<u8>>; 3],
+    open: [Vec<bool>; 3],
+    rows_read: u32,
+    y: u32,
+    graph: Graph,
+    // 4-connected: the corridor running down from the row above in each
+    // column
+    columns: Vec<Option<Corridor>>,
+    // 8-connected: the node on each pixel of the row above
+    above: Vec<Option<NodeIndex>>,
+}
+
+// associate functions
+impl<'a> RowBuilder<'a> {
+    pub fn new(options: &'a MazeOptions, width: u32) -> RowBuilder<'a> {
+        RowBuilder {
+            options,
+            width,
+            pixels: Default::default(),
+            open: Default::default(),
+            rows_read: 0,
+            y: 0,
+            graph: Graph {
+                nodes: Vec::new(),
+                keys: HashMap::new(),
+                doors: HashMap::new(),
+            },
+            columns: vec![None; width as usize],
+            above: vec![None; width as usize],
+        }
+    }
+}
+
+// methods
+impl<'a> RowBuilder<'a> {
+    /// Takes the next row as RGB bytes, three per pixel, and builds the row
+    /// before it. `forced` tells which open pixels must become nodes.
+    pub fn push_row(&mut self, row: &[u8], forced: &dyn Fn(Position, Rgb<u8>) -> bool) {
+        self.shift(row);
+        if self.rows_read > 1 {
+            self.build_row(forced);
+        }
+    }
+
+    /// Builds the last row and returns the graph.
+    pub fn finish(mut self, forced: &dyn Fn(Position, Rgb<u8>) -> bool) -> Graph {
+        if self.rows_read > 0 {
+            self.shift(&[]);
+            self.build_row(forced);
+        }
+        self.graph
+    }
+
+    fn shift(&mut self, row: &[u8]) {
+        self.pixels.rotate_left(1);
+        self.open.rotate_left(1);
+        let (pixels, open) = (&mut self.pixels[2], &mut self.open[2]);
+        pixels.clear();
+        open.clear();
+        for c in row.chunks_exact(3) {
+            let px = Rgb {
+                data: [c[0], c[1], c[2]],
+            };
+            pixels.push(px);
+            open.push(passable(self.options, px));
+        }
+        self.rows_read += 1;
+    }
+
+    /// Whether pixel `x` of row 0 (above), 1 (current) or 2 (below) is open.
+    fn is_open(&self, row: usize, x: usize) -> bool {
+        self.open[row].get(x).copied().unwrap_or(false)
+    }
+
+    fn build_row(&mut self, forced: &dyn Fn(Position, Rgb<u8>) -> bool) {
+        match self.options.connectivity {
+            Connectivity::Four => self.corridor_row(forced),
+            Connectivity::Eight { corner_cutting } => self.pixel_row(corner_cutting),
+        }
+        self.y += 1;
+    }
+
+    /// Adds a node on every junction, corner, dead end and forced pixel of
+    /// the current row, linked to the nearest node west and north along the
+    /// corridors.
+    fn corridor_row(&mut self, forced: &dyn Fn(Position, Rgb<u8>) -> bool) {
+        let (y, options) = (self.y, self.options);
+        let (terrain, keys, one_way) = (&options.terrain, &options.keys, &options.one_way);
+        let must_place = |pos: Position, px: Rgb<u8>| {
+            forced(pos, px)
+                || (!one_way.colors.is_empty() && one_way.is_one_way(px))
+                || (!keys.pairs.is_empty() && keys.is_key_or_door(px))
+        };
+
+        // track if adjacent horizontal pixels are a path
+        let mut prv: bool;
+        let mut cur = false;
+        let mut nxt = self.is_open(1, 0);
+
+        let mut left: Option<Corridor> = None;
+
+        for x in 0..self.width as usize {
+            prv = cur;
+            cur = nxt;
+            nxt = self.is_open(1, x + 1);
+
+            if !cur {
+                // on wall
+                continue;
+            }
+
+            let px = self.pixels[1][x];
+            let above = self.is_open(0, x);
+            let below = self.is_open(2, x);
+
+            // walk on along the corridors coming from the west and north
+            let cost = terrain.cost(px);
+            if prv {
+                if let Some(c) = &mut left {
+                    c.step(cost, terrain.cost(self.pixels[1][x - 1]));
+                }
+            }
+            if above {
+                if let Some(c) = &mut self.columns[x] {
+                    c.step(cost, terrain.cost(self.pixels[0][x]));
+                }
+            }
+
+            let pos = Position::new(x as u32, y);
+            let place = match (prv, nxt) {
+                // PATH PATH PATH: only where paths lead off above or below
+                (true, true) => above || below || must_place(pos, px),
+                // WALL PATH WALL: only at dead ends
+                (false, false) => !above || !below || must_place(pos, px),
+                // start or end of a corridor
+                _ => true,
+            };
+            if !place {
+                continue;
+            }
+
+            let new = self.add_node(pos, px);
+            if let Some(c) = left.take() {
+                self.link(c, new, px, Node::set_east, Node::set_west);
+            }
+            if nxt {
+                left = Some(Corridor::new(new, px));
+            }
+            // clear above, connect to the node at the top of the corridor
+            if above {
+                if let Some(c) = self.columns[x].take() {
+                    self.link(c, new, px, Node::set_south, Node::set_north);
+                }
+            }
+            // if clear below, this node starts the next corridor down
+            self.columns[x] = if below {
+                Some(Corridor::new(new, px))
+            } else {
+                None
+            };
+        }
+    }
+
+    /// Adds a node on every open pixel of the current row, linked to its
+    /// neighbors to the west, north west, north and north east.
+    fn pixel_row(&mut self, corner_cutting: bool) {
+        let y = self.y;
+        let (straight, diagonal) = self.options.connectivity.step_costs();
+        let mut row: Vec<Option<NodeIndex>> = vec![None; self.width as usize];
+
+        for x in 0..self.width as usize {
+            if !self.is_open(1, x) {
+                continue;
+            }
+            let px = self.pixels[1][x];
+            let new = self.add_node(Position::new(x as u32, y), px);
+            row[x] = Some(new);
+
+            // a diagonal step to the row above needs both pixels beside it
+            // open unless corners may be cut
+            let can_cut = |nx: usize| corner_cutting || (self.is_open(1, nx) && self.is_open(0, x));
+            let west = if x > 0 { row[x - 1] } else { None };
+            let north_west = if x > 0 { self.above[x - 1] } else { None };
+            let north_east = self.above.get(x + 1).copied().flatten();
+            let links: [(Option<NodeIndex>, usize, usize, u32, Setter, Setter); 4] = [
+                (
+                    west,
+                    1,
+                    x.wrapping_sub(1),
+                    straight,
+                    Node::set_west,
+                    Node::set_east,
+                ),
+                (
+                    north_west.filter(|_| can_cut(x - 1)),
+                    0,
+                    x.wrapping_sub(1),
+                    diagonal,
+                    Node::set_north_west,
+                    Node::set_south_east,
+                ),
+                (
+                    self.above[x],
+                    0,
+                    x,
+                    straight,
+                    Node::set_north,
+                    Node::set_south,
+                ),
+                (
+                    north_east.filter(|_| can_cut(x + 1)),
+                    0,
+                    x + 1,
+                    diagonal,
+                    Node::set_north_east,
+                    Node::set_south_west,
+                ),
+            ];
+
+            for &(other, other_row, other_x, unit, set, back) in &links {
+                let other = match other {
+                    Some(other) => other,
+                    None => continue,
+                };
+                let other_px = self.pixels[other_row][other_x];
+                self.link_step(new, px, other, other_px, unit, set, back);
+            }
+        }
+        self.above = row;
+    }
+
+    fn add_node(&mut self, pos: Position, px: Rgb<u8>) -> NodeIndex {
+        let index = self.graph.nodes.len() as NodeIndex;
+        self.graph.nodes.push(Node::new(pos.col(), pos.row()));
+
+        let keys = &self.options.keys;
+        if !keys.pairs.is_empty() {
+            if let Some(key) = keys.key(px) {
+                self.graph.keys.insert(index, key);
+            }
+            if let Some(door) = keys.door(px) {
+                self.graph.doors.insert(index, door);
+            }
+        }
+        index
+    }
+
+    /// Joins the node at the start of `corridor` to `to` at its end, in each
+    /// direction the one-way pixels allow.
+    fn link(
+        &mut self,
+        corridor: Corridor,
+        to: NodeIndex,
+        to_px: Rgb<u8>,
+        set: Setter,
+        back: Setter,
+    ) {
+        let nodes = &mut self.graph.nodes;
+        let (a, b) = (
+            nodes[corridor.node as usize].position(),
+            nodes[to as usize].position(),
+        );
+        let one_way = &self.options.one_way;
+        if one_way.allows(a, corridor.px, b, to_px) {
+            set(&mut nodes[corridor.node as usize], to, corridor.forward);
+        }
+        if one_way.allows(b, to_px, a, corridor.px) {
+            back(&mut nodes[to as usize], corridor.node, corridor.back);
+        }
+    }
+
+    /// Joins the neighboring nodes `a` and `b` by a single step costing
+    /// `unit` times the terrain cost of the pixel entered.
+    #[allow(clippy::too_many_arguments)]
+    fn link_step(
+        &mut self,
+        a: NodeIndex,
+        a_px: Rgb<u8>,
+        b: NodeIndex,
+        b_px: Rgb<u8>,
+        unit: u32,
+        set: Setter,
+        back: Setter,
+    ) {
+        let (terrain, one_way) = (&self.options.terrain, &self.options.one_way);
+        let nodes = &mut self.graph.nodes;
+        let (a_pos, b_pos) = (nodes[a as usize].position(), nodes[b as usize].position());
+        if one_way.allows(a_pos, a_px, b_pos, b_px) {
+            set(
+                &mut nodes[a as usize],
+                b,
+                terrain.cost(b_px).saturating_mul(unit),
+            );
+        }
+        if one_way.allows(b_pos, b_px, a_pos, a_px) {
+            back(
+                &mut nodes[b as usize],
+                a,
+                terrain.cost(a_px).saturating_mul(unit),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Reverse;
+    use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+    use image::{Rgb, RgbImage};
+
+    use crate::maze::node::Position;
+    use crate::maze::{Connectivity, Maze, MazeOptions};
+    use crate::pathfinder;
+
+    /// Steps from `start` to every open pixel, walking the image itself.
+    fn pixel_distances(img: &RgbImage, start: (u32, u32)) -> HashMap<(u32, u32), u64> {
+        let open = |(x, y): (u32, u32)| {
+            x < img.width() && y < img.height() && img[(x, y)].data != [0, 0, 0]
+        };
+        let mut dist = HashMap::new();
+        let mut queue = VecDeque::new();
+        dist.insert(start, 0);
+        queue.push_back(start);
+        while let Some((x, y)) = queue.pop_front() {
+            let d = dist[&(x, y)];
+            let steps = [
+                (x, y.wrapping_sub(1)),
+                (x + 1, y),
+                (x, y + 1),
+                (x.wrapping_sub(1), y),
+            ];
+            for &next in &steps {
+                if open(next) && !dist.contains_key(&next) {
+                    dist.insert(next, d + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        dist
+    }
+
+    /// Cost from the start to every node, walking the graph.
+    fn node_distances(maze: &Maze) -> Vec<Option<u64>> {
+        let mut dist = vec![None; maze.count()];
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((0, maze.start_index())));
+        while let Some(Reverse((d, n))) = queue.pop() {
+            if dist[n as usize].is_some() {
+                continue;
+            }
+            dist[n as usize] = Some(d);
+            for (m, cost) in maze.node(n).edges() {
+                queue.push(Reverse((d + u64::from(cost), m)));
+            }
+        }
+        dist
+    }
+
+    #[test]
+    fn corridors_are_as_long_as_their_pixels() {
+        let files: [&[u8]; 2] = [
+            include_bytes!("../../img/normal.png"),
+            include_bytes!("../../img/braid200.png"),
+        ];
+        for bytes in &files {
+            let img = image::load_from_memory(bytes).unwrap().to_rgb();
+            let maze = Maze::new(&img).unwrap();
+            let pixels = pixel_distances(&img, maze.start().position().as_tuple());
+            for (node, dist) in maze.nodes().iter().zip(node_distances(&maze)) {
+                let pos = node.position().as_tuple();
+                assert_eq!(dist, pixels.get(&pos).copied(), "node at {:?}", pos);
+            }
+        }
+    }
+
+    /// A maze drawn as text, `#` for walls.
+    fn draw(rows: &[&str]) -> RgbImage {
+        RgbImage::from_fn(rows[0].len() as u32, rows.len() as u32, |x, y| {
+            match rows[y as usize].as_bytes()[x as usize] {
+                b'#' => Rgb { data: [0, 0, 0] },
+                _ => Rgb {
+                    data: [255, 255, 255],
+                },
+            }
+        })
+    }
+
+    fn eight(corner_cutting: bool) -> MazeOptions {
+        MazeOptions {
+            connectivity: Connectivity::Eight { corner_cutting },
+            ..MazeOptions::default()
+        }
+    }
+
+    #[test]
+    fn diagonals_cut_corners_only_when_allowed() {
+        // the only way down squeezes between two walls
+        let img = draw(&["#.###", "#.###", "##.##", "##.##"]);
+        let maze = Maze::with_options(&img, &eight(true)).unwrap();
+        let result = pathfinder::dijk(&maze);
+        assert_eq!(result.cost, 10_000 + 14_142 + 10_000);
+        let corner = maze
+            .nodes()
+            .iter()
+            .find(|n| n.position() == Position::new(1, 1))
+            .unwrap();
+        let mut costs: Vec<u32> = corner.edges().map(|(_, cost)| cost).collect();
+        costs.sort();
+        assert_eq!(costs, vec![10_000, 14_142]);
+
+        let maze = Maze::with_options(&img, &eight(false)).unwrap();
+        assert!(pathfinder::dijk(&maze).path.is_none());
+        assert!(pathfinder::bfs(&maze).path.is_none());
+    }
+
+    #[test]
+    fn octile_estimate_keeps_astar_optimal() {
+        let img = image::load_from_memory(include_bytes!("../../img/normal.png"))
+            .unwrap()
+            .to_rgb();
+        for &corner_cutting in &[true, false] {
+            let maze = Maze::with_options(&img, &eight(corner_cutting)).unwrap();
+            let dijk = pathfinder::dijk(&maze);
+            assert!(dijk.path.is_some());
+            assert_eq!(pathfinder::astar(&maze).cost, dijk.cost);
+        }
+    }
+}
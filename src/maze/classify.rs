@@ -0,0 +1,248 @@
+//! Deciding which pixels are walls.
+
+use image::{Rgb, RgbImage};
+
+/// Whether walls are the dark or the light pixels of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Polarity {
+    /// Dark walls on a light background, the usual case.
+    DarkWalls,
+    /// Light walls on a dark background.
+    LightWalls,
+}
+
+// associate functions
+impl Polarity {
+    /// Guesses the polarity of `img` from its border, which is mostly wall in
+    /// a well formed maze.
+    pub fn detect(img: &RgbImage) -> Polarity {
+        let (width, height) = img.dimensions();
+        if width == 0 || height == 0 {
+            return Polarity::DarkWalls;
+        }
+
+        let mut total = 0u64;
+        let mut count = 0u64;
+        for x in 0..width {
+            total += u64::from(luminance(img[(x, 0)])) + u64::from(luminance(img[(x, height - 1)]));
+            count += 2;
+        }
+        for y in 0..height {
+            total += u64::from(luminance(img[(0, y)])) + u64::from(luminance(img[(width - 1, y)]));
+            count += 2;
+        }
+
+        if total / count >= 128 {
+            Polarity::LightWalls
+        } else {
+            Polarity::DarkWalls
+        }
+    }
+}
+
+/// Rule deciding which pixels are walls.
+///
+/// The default treats only pure black as wall, matching the mazes in
+/// `img/`. Scanned, compressed or anti-aliased images usually want a
+/// tolerance or a luminance threshold instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallClassifier {
+    /// Pixels within `tolerance` of `color` on every channel are walls.
+    Color { color: Rgb<u8>, tolerance: u8 },
+    /// Pixels on the wall side of a luminance `threshold` are walls: darker
+    /// than it for dark walls, brighter than it for light walls.
+    Threshold { threshold: u8, polarity: Polarity },
+}
+
+impl Default for WallClassifier {
+    fn default() -> WallClassifier {
+        WallClassifier::Color {
+            color: Rgb { data: [0, 0, 0] },
+            tolerance: 0,
+        }
+    }
+}
+
+// methods
+impl WallClassifier {
+    pub fn is_wall(&self, px: Rgb<u8>) -> bool {
+        match *self {
+            WallClassifier::Color { color, tolerance } => matches_color(px, color, tolerance),
+            WallClassifier::Threshold {
+                threshold,
+                polarity,
+            } => match polarity {
+                Polarity::DarkWalls => luminance(px) < threshold,
+                Polarity::LightWalls => luminance(px) > threshold,
+            },
+        }
+    }
+
+    /// A color this classifier always treats as a wall.
+    pub fn wall_color(&self) -> Rgb<u8> {
+        match *self {
+            WallClassifier::Color { color, .. } => color,
+            WallClassifier::Threshold { polarity, .. } => match polarity {
+                Polarity::DarkWalls => Rgb { data: [0, 0, 0] },
+                Polarity::LightWalls => Rgb {
+                    data: [255, 255, 255],
+                },
+            },
+        }
+    }
+
+    /// The wall polarity this classifier expects.
+    pub fn polarity(&self) -> Polarity {
+        match *self {
+            WallClassifier::Color { color, .. } => {
+                if luminance(color) < 128 {
+                    Polarity::DarkWalls
+                } else {
+                    Polarity::LightWalls
+                }
+            }
+            WallClassifier::Threshold { polarity, .. } => polarity,
+        }
+    }
+
+    /// Swaps dark and light walls. A wall color is replaced by its negative.
+    pub fn inverted(self) -> WallClassifier {
+        match self {
+            WallClassifier::Color { color, tolerance } => WallClassifier::Color {
+                color: Rgb {
+                    data: [255 - color[0], 255 - color[1], 255 - color[2]],
+                },
+                tolerance,
+            },
+            WallClassifier::Threshold {
+                threshold,
+                polarity,
+            } => WallClassifier::Threshold {
+                threshold,
+                polarity: match polarity {
+                    Polarity::DarkWalls => Polarity::LightWalls,
+                    Polarity::LightWalls => Polarity::DarkWalls,
+                },
+            },
+        }
+    }
+
+    /// This classifier, inverted if needed so that it expects `polarity`.
+    pub fn with_polarity(self, polarity: Polarity) -> WallClassifier {
+        if self.polarity() == polarity {
+            self
+        } else {
+            self.inverted()
+        }
+    }
+}
+
+/// Whether every channel of `px` is within `tolerance` of `color`.
+pub fn matches_color(px: Rgb<u8>, color: Rgb<u8>, tolerance: u8) -> bool {
+    px.data
+        .iter()
+        .zip(color.data.iter())
+        .all(|(&a, &b)| u8::max(a, b) - u8::min(a, b) <= tolerance)
+}
+
+/// Perceived brightness of a pixel, using the Rec. 601 weights.
+pub fn luminance(px: Rgb<u8>) -> u8 {
+    let [r, g, b] = px.data;
+    ((299 * u32::from(r) + 587 * u32::from(g) + 114 * u32::from(b)) / 1000) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(v: u8) -> Rgb<u8> {
+        Rgb { data: [v, v, v] }
+    }
+
+    /// A ring of `border` around a floor of `inside`.
+    fn framed(border: u8, inside: u8) -> RgbImage {
+        RgbImage::from_fn(5, 5, |x, y| {
+            if x == 0 || y == 0 || x == 4 || y == 4 {
+                gray(border)
+            } else {
+                gray(inside)
+            }
+        })
+    }
+
+    #[test]
+    fn color_within_tolerance() {
+        let walls = WallClassifier::Color {
+            color: Rgb { data: [10, 20, 30] },
+            tolerance: 5,
+        };
+        assert!(walls.is_wall(Rgb { data: [15, 15, 35] }));
+        assert!(!walls.is_wall(Rgb { data: [16, 20, 30] }));
+        assert!(WallClassifier::default().is_wall(gray(0)));
+        assert!(!WallClassifier::default().is_wall(gray(1)));
+        assert!(matches_color(gray(250), gray(255), 5));
+        assert!(!matches_color(gray(249), gray(255), 5));
+    }
+
+    #[test]
+    fn threshold_on_either_side() {
+        let dark = WallClassifier::Threshold {
+            threshold: 100,
+            polarity: Polarity::DarkWalls,
+        };
+        assert!(dark.is_wall(gray(99)));
+        assert!(!dark.is_wall(gray(100)));
+        // pure green is bright, pure blue is dark
+        assert!(!dark.is_wall(Rgb { data: [0, 255, 0] }));
+        assert!(dark.is_wall(Rgb { data: [0, 0, 255] }));
+
+        let light = dark.inverted();
+        assert_eq!(light.polarity(), Polarity::LightWalls);
+        assert!(light.is_wall(gray(101)));
+        assert!(!light.is_wall(gray(100)));
+        assert_eq!(light.wall_color(), gray(255));
+    }
+
+    #[test]
+    fn inverted_color_is_the_negative() {
+        let walls = WallClassifier::Color {
+            color: Rgb {
+                data: [0, 100, 255],
+            },
+            tolerance: 3,
+        };
+        assert_eq!(
+            walls.inverted(),
+            WallClassifier::Color {
+                color: Rgb {
+                    data: [255, 155, 0]
+                },
+                tolerance: 3,
+            }
+        );
+        assert_eq!(walls.inverted().inverted(), walls);
+        assert_eq!(WallClassifier::default().polarity(), Polarity::DarkWalls);
+        assert_eq!(WallClassifier::default().inverted().wall_color(), gray(255));
+    }
+
+    #[test]
+    fn with_polarity_only_inverts_when_needed() {
+        let walls = WallClassifier::default();
+        assert_eq!(walls.with_polarity(Polarity::DarkWalls), walls);
+        let light = walls.with_polarity(Polarity::LightWalls);
+        assert_eq!(light, walls.inverted());
+        assert_eq!(light.with_polarity(Polarity::LightWalls), light);
+        assert!(light.is_wall(gray(255)));
+        assert!(!light.is_wall(gray(0)));
+    }
+
+    #[test]
+    fn detects_polarity_from_the_border() {
+        assert_eq!(Polarity::detect(&framed(0, 255)), Polarity::DarkWalls);
+        // light walls on a dark floor
+        assert_eq!(Polarity::detect(&framed(255, 0)), Polarity::LightWalls);
+        assert_eq!(Polarity::detect(&framed(200, 0)), Polarity::LightWalls);
+        assert_eq!(Polarity::detect(&framed(100, 255)), Polarity::DarkWalls);
+        assert_eq!(Polarity::detect(&RgbImage::new(0, 0)), Polarity::DarkWalls);
+    }
+}
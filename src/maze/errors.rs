@@ -1,20 +1,117 @@
 use std::error::Error;
 use std::fmt::{self, Display, Formatter};
 
+use image::Rgb;
+
+use super::node::Position;
+
+/// Reasons an image could not be turned into a [`Maze`](super::Maze).
 #[derive(Debug)]
 pub enum MazeError {
     NoStartError,
     NoExitError,
+    /// A text maze contained a character other than `#`, ` `, `.`, `S` or `E`.
+    UnknownCharError(char, Position),
+    /// A text maze contained more than one `S` marker.
+    DuplicateMarkerError(char, Position),
+    /// A start or exit position lies outside the image.
+    OutOfBoundsError(Position),
+    /// A start or exit position is on a wall.
+    WallEndpointError(Position),
+    /// The image, given as width and height, is smaller than 3×3 pixels and
+    /// cannot hold a path surrounded by walls.
+    TooSmallError(u32, u32),
+    /// An opening in the border competes with the one picked as the start or
+    /// an exit.
+    ExtraEntranceError(Position),
+    /// An opening in the border that is neither the start nor an exit.
+    BorderGapError(Position),
+    /// An exit that no path from the start leads to.
+    UnreachableExitError(Position),
+    /// Open pixels, given by the first of them, that no path from the start
+    /// leads to.
+    UnreachableRegionError(Position),
+    /// A portal color found on the given pixels instead of exactly two.
+    PortalPairError(Rgb<u8>, Vec<Position>),
+    /// The floor at this index is not the same size as the first.
+    LevelSizeError(usize),
+    /// More key and door pairs than [`Keys::MAX`](super::keys::Keys::MAX).
+    TooManyKeysError(usize),
+    /// An image or its rows could not be decoded.
+    ReadError(String),
 }
 
 impl Display for MazeError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         use MazeError::*;
-        let text = match self {
-            NoStartError => "image does not contain a starting point",
-            NoExitError => "image does not contain a exit ponit",
-        };
-        write!(f, "{}", text)
+        match self {
+            NoStartError => write!(f, "image does not contain a starting point"),
+            NoExitError => write!(f, "image does not contain a exit ponit"),
+            UnknownCharError(c, pos) => write!(
+                f,
+                "unknown character '{}' at line {}, column {}",
+                c,
+                pos.row() + 1,
+                pos.col() + 1
+            ),
+            DuplicateMarkerError(c, pos) => write!(
+                f,
+                "second '{}' marker at line {}, column {}",
+                c,
+                pos.row() + 1,
+                pos.col() + 1
+            ),
+            OutOfBoundsError(pos) => write!(f, "{},{} is outside the image", pos.col(), pos.row()),
+            WallEndpointError(pos) => write!(f, "{},{} is a wall", pos.col(), pos.row()),
+            TooSmallError(width, height) => write!(
+                f,
+                "image is {}x{} pixels, a maze needs at least 3x3",
+                width, height
+            ),
+            ExtraEntranceError(pos) => {
+                write!(f, "extra entrance at {},{}", pos.col(), pos.row())
+            }
+            BorderGapError(pos) => write!(f, "gap in the border at {},{}", pos.col(), pos.row()),
+            UnreachableExitError(pos) => write!(
+                f,
+                "exit at {},{} cannot be reached from the start",
+                pos.col(),
+                pos.row()
+            ),
+            PortalPairError(color, found) => {
+                let [r, g, b] = color.data;
+                write!(
+                    f,
+                    "portal color {:02x}{:02x}{:02x} is on {} pixels, expected 2",
+                    r,
+                    g,
+                    b,
+                    found.len()
+                )?;
+                for (i, pos) in found.iter().take(4).enumerate() {
+                    let sep = if i == 0 { ": " } else { ", " };
+                    write!(f, "{}{},{}", sep, pos.col(), pos.row())?;
+                }
+                if found.len() > 4 {
+                    write!(f, ", ...")?;
+                }
+                Ok(())
+            }
+            LevelSizeError(i) => write!(f, "level {} is not the same size as level 0", i),
+            TooManyKeysError(n) => write!(
+                f,
+                "{} key colors given, at most {} are supported",
+                n,
+                super::keys::Keys::MAX
+            ),
+            UnreachableRegionError(pos) => write!(
+                f,
+                "region at {},{} cannot be reached from the start",
+                pos.col(),
+                pos.row()
+            ),
+            ReadError(msg) => write!(f, "could not read the image: {}", msg),
+        }
     }
 }
 
@@ -0,0 +1,46 @@
+//! Keys that open doors elsewhere in the maze.
+
+use image::Rgb;
+
+use super::classify::matches_color;
+
+/// The keys held at some point of a search, one bit per key.
+pub type KeySet = u64;
+
+/// Key and door colors of a maze. A door can only be stepped on once the
+/// key of its pair has been picked up by stepping on it.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Keys {
+    /// Key color and door color of each pair, at most [`Keys::MAX`].
+    pub pairs: Vec<(Rgb<u8>, Rgb<u8>)>,
+    /// How far each channel may differ from a key or door color.
+    pub tolerance: u8,
+}
+
+// associate functions
+impl Keys {
+    /// Most pairs a [`KeySet`] can tell apart.
+    pub const MAX: usize = 64;
+}
+
+// methods
+impl Keys {
+    /// Index of the pair `px` is the key of.
+    pub fn key(&self, px: Rgb<u8>) -> Option<usize> {
+        self.pairs
+            .iter()
+            .position(|&(key, _)| matches_color(px, key, self.tolerance))
+    }
+
+    /// Index of the pair `px` is the door of.
+    pub fn door(&self, px: Rgb<u8>) -> Option<usize> {
+        self.pairs
+            .iter()
+            .position(|&(_, door)| matches_color(px, door, self.tolerance))
+    }
+
+    /// Whether `px` is any key or door.
+    pub fn is_key_or_door(&self, px: Rgb<u8>) -> bool {
+        self.key(px).is_some() || self.door(px).is_some()
+    }
+}
@@ -0,0 +1,282 @@
+//! Mazes with several floors, drawn as one image per floor.
+//!
+//! The floors are stacked top to bottom into a single image, separated by a
+//! row of wall, so the usual [`Maze`](super::Maze) can be built from it.
+//! Stairs become [`Link`]s between the same pixel on adjacent floors.
+
+use image::{Rgb, RgbImage};
+
+use super::classify::{matches_color, WallClassifier};
+use super::errors::MazeError;
+use super::node::Position;
+use super::{scale, Link};
+
+/// Floor images stacked into one.
+#[derive(Debug, Clone)]
+pub struct Levels {
+    image: RgbImage,
+    width: u32,
+    height: u32,
+    count: usize,
+}
+
+// associate functions
+impl Levels {
+    /// Stacks `floors`, top floor first. Every floor must have the size of
+    /// the first one. The rows between floors are filled with a color
+    /// `walls` treats as wall.
+    pub fn stack(floors: &[RgbImage], walls: &WallClassifier) -> Result<Levels, MazeError> {
+        let (width, height) = floors.first().map_or((0, 0), RgbImage::dimensions);
+        if let Some(i) = floors
+            .iter()
+            .position(|f| f.dimensions() != (width, height))
+        {
+            return Err(MazeError::LevelSizeError(i));
+        }
+
+        let count = floors.len();
+        let total = (height + 1) * count as u32 - u32::from(count > 0);
+        let wall = walls.wall_color();
+        let image = RgbImage::from_fn(width, total, |x, y| {
+            let (level, row) = ((y / (height + 1)) as usize, y % (height + 1));
+            if row == height {
+                wall
+            } else {
+                floors[level][(x, row)]
+            }
+        });
+
+        Ok(Levels {
+            image,
+            width,
+            height,
+            count,
+        })
+    }
+}
+
+// methods
+impl Levels {
+    /// All floors as one image, to build the [`Maze`](super::Maze) from.
+    pub fn image(&self) -> &RgbImage {
+        &self.image
+    }
+
+    /// Number of floors.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Width and height of one floor.
+    pub fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    /// The floor a position of the stacked image lies on, and the position
+    /// within that floor. `None` for the rows between floors.
+    pub fn level_of(&self, pos: Position) -> Option<(usize, Position)> {
+        let level = (pos.row() / (self.height + 1)) as usize;
+        let row = pos.row() % (self.height + 1);
+        if level < self.count && row < self.height {
+            Some((level, Position::new(pos.col(), row)))
+        } else {
+            None
+        }
+    }
+
+    /// The position in the stacked image of `pos` on floor `level`.
+    pub fn to_stacked(&self, level: usize, pos: Position) -> Position {
+        Position::new(pos.col(), level as u32 * (self.height + 1) + pos.row())
+    }
+
+    /// The floors shrunk to one pixel per `cell` × `cell` block as by
+    /// [`scale::downsample`], stacked again.
+    pub fn downsample(&self, cell: u32) -> Levels {
+        let cell = cell.max(1);
+        let (width, height) = (self.width / cell, self.height / cell);
+        let count = self.count;
+        let total = (height + 1) * count as u32 - u32::from(count > 0);
+        let image = RgbImage::from_fn(width, total, |x, y| {
+            let (level, row) = ((y / (height + 1)) as usize, y % (height + 1));
+            if row == height {
+                // the wall between two floors
+                self.image[(0, self.height)]
+            } else {
+                let pos = scale::upscale(Position::new(x, row), cell);
+                self.image[self.to_stacked(level, pos).as_tuple()]
+            }
+        });
+        Levels {
+            image,
+            width,
+            height,
+            count: self.count,
+        }
+    }
+
+    /// Maps a position of the stacked image to its block in the stack
+    /// [`downsample`](Levels::downsample)d by `cell`.
+    pub fn downscale(&self, pos: Position, cell: u32) -> Position {
+        match self.level_of(pos) {
+            Some((level, pos)) => {
+                let pos = scale::downscale(pos, cell);
+                let row = level as u32 * (self.height / cell + 1) + pos.row();
+                Position::new(pos.col(), row)
+            }
+            None => scale::downscale(pos, cell),
+        }
+    }
+
+    /// Maps a position of the stack [`downsample`](Levels::downsample)d by
+    /// `cell` back to the middle of its block in the stacked image.
+    pub fn upscale(&self, pos: Position, cell: u32) -> Position {
+        let height = self.height / cell;
+        let (level, row) = (pos.row() / (height + 1), pos.row() % (height + 1));
+        let pos = scale::upscale(Position::new(pos.col(), row), cell);
+        self.to_stacked(level as usize, pos)
+    }
+
+    /// Links each pixel within `tolerance` of the stair `color` to the same
+    /// pixel on the floor below, when that is a stair too.
+    pub fn stairs(&self, color: Rgb<u8>, tolerance: u8, cost: u32) -> Vec<Link> {
+        let is_stair = |level: usize, x: u32, y: u32| {
+            let pos = self.to_stacked(level, Position::new(x, y));
+            matches_color(self.image[pos.as_tuple()], color, tolerance)
+        };
+
+        let mut links = Vec::new();
+        for level in 1..self.count {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if is_stair(level - 1, x, y) && is_stair(level, x, y) {
+                        let pos = Position::new(x, y);
+                        links.push(Link {
+                            a: self.to_stacked(level - 1, pos),
+                            b: self.to_stacked(level, pos),
+                            cost,
+                        });
+                    }
+                }
+            }
+        }
+        links
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{self, Maze, MazeOptions};
+
+    /// A floor drawn as text, `#` for walls.
+    fn floor(rows: &[&str]) -> RgbImage {
+        RgbImage::from_fn(rows[0].len() as u32, rows.len() as u32, |x, y| {
+            match rows[y as usize].as_bytes()[x as usize] {
+                b'#' => Rgb { data: [0, 0, 0] },
+                b'Y' => Rgb {
+                    data: [255, 255, 0],
+                },
+                _ => Rgb {
+                    data: [255, 255, 255],
+                },
+            }
+        })
+    }
+
+    fn stacked_floors() -> [RgbImage; 2] {
+        [
+            floor(&["###.###", "#.....#", "#..Y..#", "###.###"]),
+            floor(&["#####.#", ".....##", "#..Y..#", "#######"]),
+        ]
+    }
+
+    fn stacked() -> Levels {
+        Levels::stack(&stacked_floors(), &WallClassifier::default()).unwrap()
+    }
+
+    #[test]
+    fn floors_are_one_row_of_wall_apart() {
+        let levels = stacked();
+        assert_eq!(levels.image().dimensions(), (7, 9));
+        assert_eq!(
+            levels.level_of(Position::new(5, 5)),
+            Some((1, Position::new(5, 0)))
+        );
+        assert_eq!(levels.level_of(Position::new(3, 4)), None);
+        assert_eq!(
+            levels.to_stacked(1, Position::new(3, 2)),
+            Position::new(3, 7)
+        );
+    }
+
+    #[test]
+    fn stairs_link_the_same_pixel() {
+        let links = stacked().stairs(
+            Rgb {
+                data: [255, 255, 0],
+            },
+            0,
+            2,
+        );
+        assert_eq!(links.len(), 1);
+        assert_eq!(
+            (links[0].a, links[0].b),
+            (Position::new(3, 2), Position::new(3, 7))
+        );
+    }
+
+    #[test]
+    fn openings_on_every_floor() {
+        let levels = stacked();
+        let options = MazeOptions {
+            level_height: Some(levels.dimensions().1),
+            ..MazeOptions::default()
+        };
+        let openings = maze::openings(levels.image(), &options);
+        // the top rows, the left column, then the bottom rows
+        let expected = vec![
+            Position::new(3, 0),
+            Position::new(5, 5),
+            Position::new(0, 6),
+            Position::new(3, 3),
+        ];
+        assert_eq!(openings, expected);
+        // the streamed build finds the same ones
+        let maze = Maze::with_options(levels.image(), &options).unwrap();
+        assert_eq!(maze.openings(), &expected[..]);
+    }
+
+    #[test]
+    fn scaled_stack_downsamples_to_the_cells() {
+        let big = {
+            let floors: Vec<_> = stacked_floors()
+                .iter()
+                .map(|f| RgbImage::from_fn(14, 8, |x, y| f[(x / 2, y / 2)]))
+                .collect();
+            Levels::stack(&floors, &WallClassifier::default()).unwrap()
+        };
+        let small = big.downsample(2);
+        assert_eq!(small.dimensions(), (7, 4));
+        assert_eq!(
+            small.image().clone().into_raw(),
+            stacked().image().clone().into_raw()
+        );
+        let yellow = Rgb {
+            data: [255, 255, 0],
+        };
+        let links = small.stairs(yellow, 0, 2);
+        assert_eq!(links.len(), 1);
+        assert_eq!(links[0].b, Position::new(3, 7));
+        // the stair pixel of the second floor, in and out of cells
+        assert_eq!(big.downscale(Position::new(7, 14), 2), Position::new(3, 7));
+        assert_eq!(big.upscale(Position::new(3, 7), 2), Position::new(7, 14));
+        assert_eq!(big.upscale(Position::new(3, 2), 2), Position::new(7, 5));
+    }
+
+    #[test]
+    fn stacked_border_only_without_level_height() {
+        let levels = stacked();
+        let openings = maze::openings(levels.image(), &MazeOptions::default());
+        assert_eq!(openings, vec![Position::new(3, 0), Position::new(0, 6)]);
+    }
+}
@@ -1,12 +1,23 @@
-#![allow(unused)]
-use std::cell::RefCell;
-use std::rc::Rc;
+use crate::pathfinder::Edges;
 
+/// Index of a [`Node`] in the arena owned by its [`Maze`](super::Maze).
+pub type NodeIndex = u32;
+
+/// A junction, corner or dead end in the maze, linked to the nearest node in
+/// each of the four cardinal directions, and in 8-connected mazes to the
+/// diagonal neighbors too. Each link has a cost, the sum of the terrain costs
+/// of the pixels it enters, which may differ between the two ends. Links
+/// through one-way pixels only exist in their direction, so a neighbor need
+/// not link back.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct Node {
     position: Position,
-    // 0 = north, 1 = east, 2 = south, 3 = west
-    neighbors: [Option<Rc<RefCell<Node>>>; 4],
+    // 0 = north, 1 = east, 2 = south, 3 = west,
+    // 4 = north east, 5 = south east, 6 = south west, 7 = north west
+    neighbors: [Option<NodeIndex>; 8],
+    costs: [u32; 8],
+    // links to nodes in no particular direction, such as through a portal
+    links: Vec<(NodeIndex, u32)>,
 }
 
 // associate functions
@@ -14,7 +25,9 @@ impl Node {
     pub fn new(col: u32, row: u32) -> Node {
         Node {
             position: Position::new(col, row),
-            neighbors: [None, None, None, None],
+            neighbors: [None; 8],
+            costs: [0; 8],
+            links: Vec::new(),
         }
     }
 }
@@ -33,59 +46,139 @@ impl Node {
         self.position
     }
 
-    pub fn north(&self) -> Option<Rc<RefCell<Node>>> {
-        if let Some(n) = &self.neighbors[0] {
-            return Some(Rc::clone(n));
-        } else {
-            return None;
-        }
+    pub fn north(&self) -> Option<NodeIndex> {
+        self.neighbors[0]
     }
 
-    pub fn south(&self) -> Option<Rc<RefCell<Node>>> {
-        if let Some(n) = &self.neighbors[2] {
-            return Some(Rc::clone(n));
-        } else {
-            return None;
-        }
+    pub fn south(&self) -> Option<NodeIndex> {
+        self.neighbors[2]
     }
 
-    pub fn east(&self) -> Option<Rc<RefCell<Node>>> {
-        if let Some(n) = &self.neighbors[1] {
-            return Some(Rc::clone(n));
-        } else {
-            return None;
-        }
+    pub fn east(&self) -> Option<NodeIndex> {
+        self.neighbors[1]
     }
 
-    pub fn west(&self) -> Option<Rc<RefCell<Node>>> {
-        if let Some(n) = &self.neighbors[3] {
-            return Some(Rc::clone(n));
-        } else {
-            return None;
-        }
+    pub fn west(&self) -> Option<NodeIndex> {
+        self.neighbors[3]
+    }
+
+    pub fn north_east(&self) -> Option<NodeIndex> {
+        self.neighbors[4]
+    }
+
+    pub fn south_east(&self) -> Option<NodeIndex> {
+        self.neighbors[5]
     }
 
-    pub fn set_north(&mut self, neighbor: Rc<RefCell<Node>>) {
+    pub fn south_west(&self) -> Option<NodeIndex> {
+        self.neighbors[6]
+    }
+
+    pub fn north_west(&self) -> Option<NodeIndex> {
+        self.neighbors[7]
+    }
+
+    pub(crate) fn set_north(&mut self, neighbor: NodeIndex, cost: u32) {
         self.neighbors[0] = Some(neighbor);
+        self.costs[0] = cost;
     }
 
-    pub fn set_south(&mut self, neighbor: Rc<RefCell<Node>>) {
+    pub(crate) fn set_south(&mut self, neighbor: NodeIndex, cost: u32) {
         self.neighbors[2] = Some(neighbor);
+        self.costs[2] = cost;
     }
 
-    pub fn set_east(&mut self, neighbor: Rc<RefCell<Node>>) {
+    pub(crate) fn set_east(&mut self, neighbor: NodeIndex, cost: u32) {
         self.neighbors[1] = Some(neighbor);
+        self.costs[1] = cost;
     }
 
-    pub fn set_west(&mut self, neighbor: Rc<RefCell<Node>>) {
+    pub(crate) fn set_west(&mut self, neighbor: NodeIndex, cost: u32) {
         self.neighbors[3] = Some(neighbor);
+        self.costs[3] = cost;
+    }
+
+    pub(crate) fn set_north_east(&mut self, neighbor: NodeIndex, cost: u32) {
+        self.neighbors[4] = Some(neighbor);
+        self.costs[4] = cost;
+    }
+
+    pub(crate) fn set_south_east(&mut self, neighbor: NodeIndex, cost: u32) {
+        self.neighbors[5] = Some(neighbor);
+        self.costs[5] = cost;
     }
 
-    pub fn neighbors(&self) -> &[Option<Rc<RefCell<Node>>>] {
+    pub(crate) fn set_south_west(&mut self, neighbor: NodeIndex, cost: u32) {
+        self.neighbors[6] = Some(neighbor);
+        self.costs[6] = cost;
+    }
+
+    pub(crate) fn set_north_west(&mut self, neighbor: NodeIndex, cost: u32) {
+        self.neighbors[7] = Some(neighbor);
+        self.costs[7] = cost;
+    }
+
+    /// Neighbors in north, east, south, west order, followed by north east,
+    /// south east, south west and north west. The diagonals are only linked
+    /// in 8-connected mazes.
+    pub fn neighbors(&self) -> &[Option<NodeIndex>] {
         &self.neighbors
     }
+
+    /// Cost of following the link to each neighbor, in the same order as
+    /// [`neighbors`](Node::neighbors). Zero where there is no link.
+    pub fn costs(&self) -> &[u32] {
+        &self.costs
+    }
+
+    /// Links that do not follow a corridor, such as portal jumps, with the
+    /// cost of each.
+    pub fn links(&self) -> &[(NodeIndex, u32)] {
+        &self.links
+    }
+
+    pub(crate) fn add_link(&mut self, neighbor: NodeIndex, cost: u32) {
+        self.links.push((neighbor, cost));
+    }
+
+    /// Every neighbor with the cost of moving to it: the eight directions
+    /// first, then the other [`links`](Node::links).
+    pub fn edges(&self) -> Edges<'_> {
+        Edges::new(&self.neighbors, &self.costs, &self.links)
+    }
+
+    /// Cost of the cheapest link to `neighbor`, if there is one.
+    pub fn cost_to(&self, neighbor: NodeIndex) -> Option<u32> {
+        self.edges()
+            .filter(|&(n, _)| n == neighbor)
+            .map(|(_, c)| c)
+            .min()
+    }
+
+    /// Whether the cheapest way to `neighbor` is one of the other
+    /// [`links`](Node::links) rather than a corridor.
+    pub fn jumps_to(&self, neighbor: NodeIndex) -> bool {
+        let link = self
+            .links
+            .iter()
+            .filter(|&&(n, _)| n == neighbor)
+            .map(|&(_, c)| c)
+            .min();
+        let corridor = self
+            .neighbors
+            .iter()
+            .zip(&self.costs)
+            .filter(|&(&n, _)| n == Some(neighbor))
+            .map(|(_, &c)| c)
+            .min();
+        match (link, corridor) {
+            (Some(l), Some(c)) => l < c,
+            (link, _) => link.is_some(),
+        }
+    }
 }
 
+/// A pixel coordinate in the source image.
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Position {
     col: u32,
@@ -0,0 +1,142 @@
+//! One-way pixels that may only be crossed in one direction.
+
+use image::Rgb;
+
+use super::classify::matches_color;
+use super::node::Position;
+
+/// A direction on the image. North is up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+// associate functions
+impl Direction {
+    /// The direction of a straight step from `a` to `b`. `None` for diagonal
+    /// steps, jumps and standing still.
+    pub fn of_step(a: Position, b: Position) -> Option<Direction> {
+        if a.row() == b.row() && a.col() < b.col() {
+            Some(Direction::East)
+        } else if a.row() == b.row() && a.col() > b.col() {
+            Some(Direction::West)
+        } else if a.col() == b.col() && a.row() < b.row() {
+            Some(Direction::South)
+        } else if a.col() == b.col() && a.row() > b.row() {
+            Some(Direction::North)
+        } else {
+            None
+        }
+    }
+}
+
+/// Colors marking pixels that can only be crossed in one direction, such as
+/// one-way doors. A step onto or off such a pixel must go its way, so it can
+/// only be entered from behind and left ahead.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OneWay {
+    /// Each color with the direction its pixels may be crossed in.
+    pub colors: Vec<(Direction, Rgb<u8>)>,
+    /// How far each channel may differ from a one-way color.
+    pub tolerance: u8,
+}
+
+// methods
+impl OneWay {
+    /// The direction `px` may be crossed in, or `None` if it is not a
+    /// one-way pixel.
+    pub fn direction(&self, px: Rgb<u8>) -> Option<Direction> {
+        self.colors
+            .iter()
+            .find(|&&(_, color)| matches_color(px, color, self.tolerance))
+            .map(|&(dir, _)| dir)
+    }
+
+    /// Whether a step from `a`, colored `a_px`, to `b`, colored `b_px`, may
+    /// be taken. Steps onto or off a one-way pixel must go its way.
+    pub fn allows(&self, a: Position, a_px: Rgb<u8>, b: Position, b_px: Rgb<u8>) -> bool {
+        if self.colors.is_empty() {
+            return true;
+        }
+        let dir = Direction::of_step(a, b);
+        [a_px, b_px]
+            .iter()
+            .all(|&px| self.direction(px).is_none_or(|way| Some(way) == dir))
+    }
+
+    /// Whether `px` is a one-way pixel.
+    pub fn is_one_way(&self, px: Rgb<u8>) -> bool {
+        self.direction(px).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{Maze, MazeOptions};
+    use crate::pathfinder;
+    use image::RgbImage;
+
+    const RED: Rgb<u8> = Rgb { data: [255, 0, 0] };
+
+    /// The straight way down passes a red one-way pixel pointing `way`, and
+    /// a longer corridor goes around it.
+    fn maze(way: Direction) -> Maze {
+        let rows = [
+            "#.#####", "#.....#", "#o###.#", "#.###.#", "#.....#", "#.#####",
+        ];
+        let img = RgbImage::from_fn(7, 6, |x, y| match rows[y as usize].as_bytes()[x as usize] {
+            b'#' => Rgb { data: [0, 0, 0] },
+            b'o' => RED,
+            _ => Rgb {
+                data: [255, 255, 255],
+            },
+        });
+        let options = MazeOptions {
+            one_way: OneWay {
+                colors: vec![(way, RED)],
+                tolerance: 0,
+            },
+            ..MazeOptions::default()
+        };
+        Maze::with_options(&img, &options).unwrap()
+    }
+
+    #[test]
+    fn steps_must_go_the_pixels_way() {
+        let one_way = OneWay {
+            colors: vec![(Direction::North, RED)],
+            tolerance: 0,
+        };
+        let white = Rgb {
+            data: [255, 255, 255],
+        };
+        let (a, b, c) = (
+            Position::new(1, 1),
+            Position::new(1, 2),
+            Position::new(1, 3),
+        );
+        assert!(one_way.allows(c, white, b, RED));
+        assert!(one_way.allows(b, RED, a, white));
+        assert!(!one_way.allows(a, white, b, RED));
+        assert!(!one_way.allows(b, RED, Position::new(2, 2), white));
+        assert!(one_way.allows(a, white, Position::new(2, 1), white));
+    }
+
+    #[test]
+    fn reverse_route_takes_the_detour() {
+        let against = maze(Direction::North);
+        let along = maze(Direction::South);
+        let solvers: [fn(&dyn pathfinder::Graph) -> pathfinder::SolveResult; 3] =
+            [pathfinder::bfs, pathfinder::dijk, pathfinder::astar];
+        for solve in &solvers {
+            assert_eq!(solve(&along).cost, 5);
+            let result = solve(&against);
+            assert_eq!(result.cost, 13);
+            assert!(result.path.unwrap().contains(&Position::new(5, 1)));
+        }
+    }
+}
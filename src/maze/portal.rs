@@ -0,0 +1,142 @@
+//! Pairs of pixels that teleport to each other.
+
+use std::collections::HashMap;
+
+use image::{Rgb, RgbImage};
+
+use super::classify::{matches_color, WallClassifier};
+use super::node::Position;
+
+/// Portal colors of a maze. Every color marks one pair of pixels and stepping
+/// onto either of them may jump to the other.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Portals {
+    /// One color per pair. Each must appear on exactly two pixels.
+    pub colors: Vec<Rgb<u8>>,
+    /// How far each channel may differ from a portal color.
+    pub tolerance: u8,
+    /// Cost of jumping from one pixel of a pair to the other.
+    pub cost: u32,
+}
+
+// associate functions
+impl Portals {
+    /// Guesses the portal colors of `img`: every color other than the walls
+    /// and the background that appears on exactly two pixels.
+    pub fn detect(img: &RgbImage, walls: &WallClassifier) -> Vec<Rgb<u8>> {
+        let mut counts: HashMap<[u8; 3], usize> = HashMap::new();
+        for px in img.pixels() {
+            if !walls.is_wall(*px) {
+                *counts.entry(px.data).or_insert(0) += 1;
+            }
+        }
+        let background = counts.iter().max_by_key(|&(_, &n)| n).map(|(&c, _)| c);
+
+        let mut colors: Vec<[u8; 3]> = counts
+            .into_iter()
+            .filter(|&(c, n)| n == 2 && Some(c) != background)
+            .map(|(c, _)| c)
+            .collect();
+        colors.sort_unstable();
+        colors.into_iter().map(|data| Rgb { data }).collect()
+    }
+}
+
+// methods
+impl Portals {
+    /// Whether `px` belongs to any portal.
+    pub fn is_portal(&self, px: Rgb<u8>) -> bool {
+        self.colors
+            .iter()
+            .any(|&c| matches_color(px, c, self.tolerance))
+    }
+
+    /// The pixels of each portal color in `img`, in the order of
+    /// [`colors`](Portals::colors). Rows are scanned top to bottom.
+    pub fn find(&self, img: &RgbImage) -> Vec<Vec<Position>> {
+        let mut found = vec![Vec::new(); self.colors.len()];
+        if self.colors.is_empty() {
+            return found;
+        }
+        for (x, y, px) in img.enumerate_pixels() {
+            for (i, &c) in self.colors.iter().enumerate() {
+                if matches_color(*px, c, self.tolerance) {
+                    found[i].push(Position::new(x, y));
+                }
+            }
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{Maze, MazeOptions};
+    use crate::pathfinder;
+
+    const CYAN: Rgb<u8> = Rgb {
+        data: [0, 200, 255],
+    };
+
+    /// A corridor from the start on top to the exit on the right. The
+    /// portal at its far left end jumps to the pixel beside the exit.
+    fn corridor() -> RgbImage {
+        let rows = [
+            "#########.###########",
+            "#P.................P.",
+            "#####################",
+        ];
+        RgbImage::from_fn(21, 3, |x, y| {
+            match rows[y as usize].as_bytes()[x as usize] {
+                b'#' => Rgb { data: [0, 0, 0] },
+                b'P' => CYAN,
+                _ => Rgb {
+                    data: [255, 255, 255],
+                },
+            }
+        })
+    }
+
+    #[test]
+    fn detects_colors_on_two_pixels() {
+        let mut img = corridor();
+        assert_eq!(
+            Portals::detect(&img, &WallClassifier::default()),
+            vec![CYAN]
+        );
+        img[(5, 1)] = CYAN;
+        assert!(Portals::detect(&img, &WallClassifier::default()).is_empty());
+    }
+
+    #[test]
+    fn astar_takes_the_portal_like_dijk() {
+        let img = corridor();
+        for &cost in &[0, 1] {
+            let options = MazeOptions {
+                portals: Portals {
+                    colors: Portals::detect(&img, &WallClassifier::default()),
+                    tolerance: 0,
+                    cost,
+                },
+                ..MazeOptions::default()
+            };
+            let maze = Maze::with_options(&img, &options).unwrap();
+            let dijk = pathfinder::dijk(&maze);
+            // walking straight to the exit would cost 12, and the portal lies
+            // too far from the exit for a plain distance estimate
+            assert_eq!(dijk.cost, 10 + u64::from(cost));
+            let astar = pathfinder::astar(&maze);
+            assert_eq!(astar.cost, dijk.cost);
+            let expected = vec![
+                Position::new(9, 0),
+                Position::new(9, 1),
+                Position::new(1, 1),
+                Position::new(19, 1),
+                Position::new(20, 1),
+            ];
+            assert_eq!(astar.path.unwrap(), expected);
+            assert_eq!(astar.jumps, vec![2]);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Mazes drawn with cells larger than one pixel.
+//!
+//! A maze rendered with 10×10 pixel cells builds ten times as many nodes in
+//! each direction as it needs, and its solution hugs the corridor edges. These
+//! functions shrink such an image to one pixel per cell before building the
+//! [`Maze`](super::Maze), and scale the solution back up afterwards.
+
+use image::RgbImage;
+use num::integer::gcd;
+
+use super::classify::WallClassifier;
+use super::node::Position;
+
+/// Guesses the size in pixels of one maze cell in `img`.
+///
+/// Every run of wall or path pixels along a row or a column of a scaled up
+/// maze spans a whole number of cells, so the cell size is the greatest
+/// common divisor of the run lengths and of the image size. Images whose
+/// walls are thinner than their corridors come out as 1.
+pub fn detect_cell_size(img: &RgbImage, walls: &WallClassifier) -> u32 {
+    let (width, height) = img.dimensions();
+    let mut size = gcd(width, height);
+
+    for y in 0..height {
+        size = gcd(
+            size,
+            run_gcd((0..width).map(|x| walls.is_wall(img[(x, y)]))),
+        );
+        if size <= 1 {
+            return 1;
+        }
+    }
+    for x in 0..width {
+        size = gcd(
+            size,
+            run_gcd((0..height).map(|y| walls.is_wall(img[(x, y)]))),
+        );
+        if size <= 1 {
+            return 1;
+        }
+    }
+    size.max(1)
+}
+
+/// Shrinks `img` to one pixel per `cell` × `cell` block, taking the pixel at
+/// the middle of each block.
+pub fn downsample(img: &RgbImage, cell: u32) -> RgbImage {
+    let cell = cell.max(1);
+    RgbImage::from_fn(img.width() / cell, img.height() / cell, |x, y| {
+        img[(x * cell + cell / 2, y * cell + cell / 2)]
+    })
+}
+
+/// Maps a position in an image downsampled by `cell` back to the middle of
+/// its block in the original image.
+pub fn upscale(pos: Position, cell: u32) -> Position {
+    Position::new(pos.col() * cell + cell / 2, pos.row() * cell + cell / 2)
+}
+
+/// Maps a position in the original image to its block in an image
+/// downsampled by `cell`.
+pub fn downscale(pos: Position, cell: u32) -> Position {
+    Position::new(pos.col() / cell, pos.row() / cell)
+}
+
+/// [`upscale`] applied to every position of a path, which keeps it running
+/// through the middle of the corridors.
+pub fn upscale_path(path: &[Position], cell: u32) -> Vec<Position> {
+    path.iter().map(|&pos| upscale(pos, cell)).collect()
+}
+
+/// Greatest common divisor of the lengths of the runs of equal values.
+fn run_gcd(line: impl Iterator<Item = bool>) -> u32 {
+    let mut size = 0;
+    let mut run = 0;
+    let mut prv = None;
+    for cur in line {
+        if prv.is_some() && prv != Some(cur) {
+            size = gcd(size, run);
+            run = 0;
+        }
+        run += 1;
+        prv = Some(cur);
+    }
+    gcd(size, run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    const WALL: Rgb<u8> = Rgb { data: [0, 0, 0] };
+    const PATH: Rgb<u8> = Rgb {
+        data: [255, 255, 255],
+    };
+
+    /// A maze drawn as text, `#` for walls, with `cell` pixels per character.
+    fn maze(rows: &[&str], cell: u32) -> RgbImage {
+        let (width, height) = (rows[0].len() as u32, rows.len() as u32);
+        RgbImage::from_fn(width * cell, height * cell, |x, y| {
+            match rows[(y / cell) as usize].as_bytes()[(x / cell) as usize] {
+                b'#' => WALL,
+                _ => PATH,
+            }
+        })
+    }
+
+    const ROWS: &[&str] = &["#.###", "#...#", "###.#"];
+
+    #[test]
+    fn detects_scaled_cells() {
+        let walls = WallClassifier::default();
+        assert_eq!(detect_cell_size(&maze(ROWS, 1), &walls), 1);
+        assert_eq!(detect_cell_size(&maze(ROWS, 4), &walls), 4);
+        assert_eq!(detect_cell_size(&maze(ROWS, 6), &walls), 6);
+    }
+
+    #[test]
+    fn odd_run_means_no_cells() {
+        let mut img = maze(ROWS, 4);
+        // one wall pixel short on a single row
+        img[(0, 5)] = PATH;
+        assert_eq!(detect_cell_size(&img, &WallClassifier::default()), 1);
+    }
+
+    #[test]
+    fn downsample_restores_the_maze() {
+        assert_eq!(
+            downsample(&maze(ROWS, 5), 5).into_raw(),
+            maze(ROWS, 1).into_raw()
+        );
+    }
+
+    #[test]
+    fn positions_map_to_the_middle_of_cells() {
+        assert_eq!(upscale(Position::new(1, 2), 5), Position::new(7, 12));
+        assert_eq!(upscale(Position::new(1, 2), 4), Position::new(6, 10));
+        assert_eq!(downscale(Position::new(9, 10), 5), Position::new(1, 2));
+        assert_eq!(
+            downscale(upscale(Position::new(3, 1), 7), 7),
+            Position::new(3, 1)
+        );
+        assert_eq!(
+            upscale_path(&[Position::new(1, 0), Position::new(1, 1)], 3),
+            vec![Position::new(4, 1), Position::new(4, 4)]
+        );
+    }
+
+    #[test]
+    fn run_lengths() {
+        let line = |s: &str| run_gcd(s.chars().map(|c| c == '#'));
+        assert_eq!(line("##....##"), 2);
+        assert_eq!(line("###...###"), 3);
+        assert_eq!(line("##...##"), 1);
+        assert_eq!(line("....."), 5);
+    }
+}
@@ -0,0 +1,409 @@
+//! Images read one row at a time.
+//!
+//! [`Maze::from_rows`](super::Maze::from_rows) builds the graph from any
+//! [`RowSource`] without holding the whole image in memory, which matters for
+//! scanned mazes tens of thousands of pixels across. PNG files are decoded
+//! row by row and Netpbm files (PBM, PGM and PPM) are parsed as they are
+//! read.
+
+use std::io::{BufRead, Read};
+
+use image::RgbImage;
+
+use super::errors::MazeError;
+
+/// An image handed out one row at a time, top to bottom.
+pub trait RowSource {
+    /// Width and height of the image in pixels.
+    fn dimensions(&self) -> (u32, u32);
+
+    /// Reads the next row into `row` as RGB bytes, three per pixel. `row`
+    /// holds exactly one row.
+    fn read_row(&mut self, row: &mut [u8]) -> Result<(), MazeError>;
+}
+
+/// The rows of an image already in memory.
+#[derive(Debug, Clone)]
+pub struct ImageRows<'a> {
+    img: &'a RgbImage,
+    y: u32,
+}
+
+// associate functions
+impl<'a> ImageRows<'a> {
+    pub fn new(img: &'a RgbImage) -> ImageRows<'a> {
+        ImageRows { img, y: 0 }
+    }
+}
+
+impl<'a> RowSource for ImageRows<'a> {
+    fn dimensions(&self) -> (u32, u32) {
+        self.img.dimensions()
+    }
+
+    fn read_row(&mut self, row: &mut [u8]) -> Result<(), MazeError> {
+        let len = row.len();
+        let start = self.y as usize * len;
+        row.copy_from_slice(&(**self.img)[start..start + len]);
+        self.y += 1;
+        Ok(())
+    }
+}
+
+/// The rows of a PNG file, decoded as they are read. Interlaced files cannot
+/// be read row by row and are rejected.
+pub struct PngRows<R: Read> {
+    reader: png::Reader<R>,
+    width: u32,
+    height: u32,
+    color_type: png::ColorType,
+}
+
+// associate functions
+impl<R: Read> PngRows<R> {
+    /// Reads the header of the PNG file in `r`.
+    pub fn new(r: R) -> Result<PngRows<R>, MazeError> {
+        let (info, reader) = png::Decoder::new(r).read_info().map_err(read_error)?;
+        if reader.info().interlaced {
+            return Err(MazeError::ReadError(
+                "interlaced PNG files cannot be read row by row".to_owned(),
+            ));
+        }
+        Ok(PngRows {
+            reader,
+            width: info.width,
+            height: info.height,
+            color_type: info.color_type,
+        })
+    }
+}
+
+impl<R: Read> RowSource for PngRows<R> {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn read_row(&mut self, row: &mut [u8]) -> Result<(), MazeError> {
+        use png::ColorType::*;
+
+        let data = self
+            .reader
+            .next_row()
+            .map_err(read_error)?
+            .ok_or_else(|| MazeError::ReadError("PNG file ended early".to_owned()))?;
+        // palettes and low bit depths are expanded and 16 bit channels
+        // stripped by the decoder, leaving one byte per channel
+        let channels = match self.color_type {
+            Grayscale => 1,
+            GrayscaleAlpha => 2,
+            RGB | Indexed => 3,
+            RGBA => 4,
+        };
+        for (px, c) in row.chunks_exact_mut(3).zip(data.chunks(channels)) {
+            match channels {
+                1 | 2 => px.copy_from_slice(&[c[0]; 3]),
+                _ => px.copy_from_slice(&c[..3]),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The rows of a binary or plain Netpbm file: a PBM bitmap (`P1`, `P4`), a
+/// PGM gray map (`P2`, `P5`) or a PPM pixel map (`P3`, `P6`). Only maximum
+/// values up to 255 are supported.
+pub struct PnmRows<R: BufRead> {
+    reader: R,
+    kind: u8,
+    width: u32,
+    height: u32,
+    max: u32,
+    buf: Vec<u8>,
+}
+
+// associate functions
+impl<R: BufRead> PnmRows<R> {
+    /// Reads the header of the Netpbm file in `reader`.
+    pub fn new(mut reader: R) -> Result<PnmRows<R>, MazeError> {
+        let magic = token(&mut reader)?;
+        let kind = match magic.as_bytes() {
+            [b'P', k @ b'1'..=b'6'] => k - b'0',
+            _ => return Err(MazeError::ReadError("not a Netpbm file".to_owned())),
+        };
+        let width = number(&mut reader)?;
+        let height = number(&mut reader)?;
+        let max = if kind == 1 || kind == 4 {
+            1
+        } else {
+            number(&mut reader)?
+        };
+        if max == 0 || max > 255 {
+            return Err(MazeError::ReadError(format!(
+                "maximum value {} is not supported",
+                max
+            )));
+        }
+        // a single whitespace byte separates the header from binary data,
+        // and was consumed with the last number
+
+        Ok(PnmRows {
+            reader,
+            kind,
+            width,
+            height,
+            max,
+            buf: Vec::new(),
+        })
+    }
+}
+
+impl<R: BufRead> RowSource for PnmRows<R> {
+    fn dimensions(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn read_row(&mut self, row: &mut [u8]) -> Result<(), MazeError> {
+        let width = self.width as usize;
+        let max = self.max;
+        let scale = |v: u32| (v.min(max) * 255 / max) as u8;
+        match self.kind {
+            // PBM: 1 is black
+            1 => {
+                for px in row.chunks_exact_mut(3) {
+                    let bit = bit(&mut self.reader)?;
+                    px.copy_from_slice(&[if bit { 0 } else { 255 }; 3]);
+                }
+            }
+            4 => {
+                self.buf.resize(width.div_ceil(8), 0);
+                self.reader.read_exact(&mut self.buf).map_err(read_error)?;
+                for (x, px) in row.chunks_exact_mut(3).enumerate() {
+                    let bit = self.buf[x / 8] & (0x80 >> (x % 8)) != 0;
+                    px.copy_from_slice(&[if bit { 0 } else { 255 }; 3]);
+                }
+            }
+            2 => {
+                for px in row.chunks_exact_mut(3) {
+                    let v = scale(number(&mut self.reader)?);
+                    px.copy_from_slice(&[v; 3]);
+                }
+            }
+            5 => {
+                self.buf.resize(width, 0);
+                self.reader.read_exact(&mut self.buf).map_err(read_error)?;
+                for (px, &v) in row.chunks_exact_mut(3).zip(&self.buf) {
+                    px.copy_from_slice(&[scale(u32::from(v)); 3]);
+                }
+            }
+            3 => {
+                for v in row.iter_mut() {
+                    *v = scale(number(&mut self.reader)?);
+                }
+            }
+            _ => {
+                self.buf.resize(width * 3, 0);
+                self.reader.read_exact(&mut self.buf).map_err(read_error)?;
+                for (v, &b) in row.iter_mut().zip(&self.buf) {
+                    *v = scale(u32::from(b));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_error(e: impl std::fmt::Display) -> MazeError {
+    MazeError::ReadError(e.to_string())
+}
+
+/// The next byte of `reader`, if any.
+fn next_byte(reader: &mut impl BufRead) -> Result<Option<u8>, MazeError> {
+    let byte = reader.fill_buf().map_err(read_error)?.first().copied();
+    if byte.is_some() {
+        reader.consume(1);
+    }
+    Ok(byte)
+}
+
+/// The next whitespace separated word of a Netpbm header, skipping comments,
+/// along with the one whitespace byte after it.
+fn token(reader: &mut impl BufRead) -> Result<String, MazeError> {
+    let mut word = String::new();
+    let mut comment = false;
+    while let Some(b) = next_byte(reader)? {
+        match b {
+            b'\n' | b'\r' if comment => comment = false,
+            _ if comment => {}
+            b'#' => comment = true,
+            _ if b.is_ascii_whitespace() => {
+                if !word.is_empty() {
+                    return Ok(word);
+                }
+            }
+            _ => word.push(b as char),
+        }
+    }
+    if word.is_empty() {
+        Err(MazeError::ReadError("Netpbm file ended early".to_owned()))
+    } else {
+        Ok(word)
+    }
+}
+
+fn number(reader: &mut impl BufRead) -> Result<u32, MazeError> {
+    let word = token(reader)?;
+    word.parse()
+        .map_err(|_| MazeError::ReadError(format!("{} is not a number", word)))
+}
+
+/// The next pixel of a plain PBM file, where digits need not be separated.
+fn bit(reader: &mut impl BufRead) -> Result<bool, MazeError> {
+    loop {
+        match next_byte(reader)? {
+            Some(b'0') => return Ok(false),
+            Some(b'1') => return Ok(true),
+            Some(b'#') => while !matches!(next_byte(reader)?, Some(b'\n') | None) {},
+            Some(b) if b.is_ascii_whitespace() => {}
+            _ => return Err(MazeError::ReadError("bad PBM pixel".to_owned())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{Maze, MazeOptions};
+    use crate::pathfinder;
+
+    const NORMAL: &[u8] = include_bytes!("../../img/normal.png");
+
+    fn normal() -> RgbImage {
+        image::load_from_memory(NORMAL).unwrap().to_rgb()
+    }
+
+    /// Every row of `source`, one after the other.
+    fn read_all(source: &mut dyn RowSource) -> Vec<u8> {
+        let (width, height) = source.dimensions();
+        let mut all = Vec::new();
+        let mut row = vec![0; width as usize * 3];
+        for _ in 0..height {
+            source.read_row(&mut row).unwrap();
+            all.extend_from_slice(&row);
+        }
+        all
+    }
+
+    /// `img` as a Netpbm file of `kind`, whose pixels must be black or white
+    /// for a bitmap.
+    fn pnm(img: &RgbImage, kind: u8) -> Vec<u8> {
+        let (width, height) = img.dimensions();
+        let mut out = format!("P{}\n# a comment\n{} {}\n", kind, width, height).into_bytes();
+        if kind != 1 && kind != 4 {
+            out.extend_from_slice(b"255\n");
+        }
+        for y in 0..height {
+            let row = (0..width).map(|x| img[(x, y)].data);
+            match kind {
+                1 => {
+                    for px in row {
+                        out.extend_from_slice(if px[0] == 0 { b"1" } else { b"0" });
+                    }
+                    out.push(b'\n');
+                }
+                4 => {
+                    let mut bits = vec![0u8; (width as usize).div_ceil(8)];
+                    for (x, px) in row.enumerate() {
+                        if px[0] == 0 {
+                            bits[x / 8] |= 0x80 >> (x % 8);
+                        }
+                    }
+                    out.extend_from_slice(&bits);
+                }
+                2 => {
+                    for px in row {
+                        out.extend_from_slice(format!("{} ", px[0]).as_bytes());
+                    }
+                    out.push(b'\n');
+                }
+                5 => out.extend(row.map(|px| px[0])),
+                3 => {
+                    for px in row {
+                        out.extend_from_slice(format!("{} {} {} ", px[0], px[1], px[2]).as_bytes());
+                    }
+                    out.push(b'\n');
+                }
+                _ => out.extend(row.flatten()),
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn every_netpbm_kind_reads_like_the_image() {
+        let img = normal();
+        let expected = read_all(&mut ImageRows::new(&img));
+        for kind in 1..=6 {
+            let bytes = pnm(&img, kind);
+            let mut rows = PnmRows::new(&bytes[..]).unwrap();
+            assert_eq!(rows.dimensions(), img.dimensions(), "P{}", kind);
+            assert_eq!(read_all(&mut rows), expected, "P{}", kind);
+        }
+    }
+
+    #[test]
+    fn netpbm_values_are_scaled_to_255() {
+        let mut rows = PnmRows::new(&b"P2 3 1 15\n0 15 5\n"[..]).unwrap();
+        assert_eq!(
+            read_all(&mut rows),
+            vec![0, 0, 0, 255, 255, 255, 85, 85, 85]
+        );
+    }
+
+    #[test]
+    fn plain_bitmap_digits_need_no_spaces() {
+        let mut rows = PnmRows::new(&b"P1\n3 2\n010\n1 0 1\n"[..]).unwrap();
+        let all = read_all(&mut rows);
+        let black: Vec<bool> = all.chunks(3).map(|px| px[0] == 0).collect();
+        assert_eq!(black, vec![false, true, false, true, false, true]);
+    }
+
+    #[test]
+    fn bad_netpbm_files() {
+        assert!(PnmRows::new(&b"P7\n1 1\n"[..]).is_err());
+        assert!(PnmRows::new(&b"P2\n1 1\n65535\n0\n"[..]).is_err());
+        let mut rows = PnmRows::new(&b"P5\n4 1\n255\n\x00"[..]).unwrap();
+        assert!(rows.read_row(&mut [0; 12]).is_err());
+    }
+
+    #[test]
+    fn png_reads_like_the_image() {
+        let img = normal();
+        let mut rows = PngRows::new(NORMAL).unwrap();
+        assert_eq!(read_all(&mut rows), read_all(&mut ImageRows::new(&img)));
+    }
+
+    #[test]
+    fn streamed_maze_is_the_same_graph() {
+        let img = normal();
+        let options = MazeOptions::default();
+        let in_memory = Maze::with_options(&img, &options).unwrap();
+        let bytes = pnm(&img, 6);
+        let sources: Vec<Box<dyn RowSource>> = vec![
+            Box::new(PngRows::new(NORMAL).unwrap()),
+            Box::new(PnmRows::new(&bytes[..]).unwrap()),
+        ];
+        for mut source in sources {
+            let streamed = Maze::from_rows(source.as_mut(), &options).unwrap();
+            assert_eq!(streamed.count(), in_memory.count());
+            for (a, b) in streamed.nodes().iter().zip(in_memory.nodes()) {
+                assert_eq!(a.position(), b.position());
+                assert_eq!(a.edges().collect::<Vec<_>>(), b.edges().collect::<Vec<_>>());
+            }
+            assert_eq!(streamed.openings(), in_memory.openings());
+            assert_eq!(
+                pathfinder::astar(&streamed).path,
+                pathfinder::astar(&in_memory).path
+            );
+        }
+    }
+}
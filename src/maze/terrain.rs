@@ -0,0 +1,120 @@
+//! Traversal costs for the open pixels of a maze.
+
+use image::Rgb;
+
+use super::classify::{luminance, matches_color};
+
+/// Rule giving each open pixel the cost of stepping onto it. Costs are at
+/// least 1, so distance heuristics never overestimate the remaining cost.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum Terrain {
+    /// Every pixel costs 1, so the cost of a path is its length in pixels.
+    #[default]
+    Uniform,
+    /// Darker pixels cost more: white costs 1 and black costs `max`, with
+    /// the grays in between scaled linearly.
+    Grayscale { max: u32 },
+    /// Pixels within `tolerance` of one of the colors cost the amount paired
+    /// with it, such as road, mud or water. Other pixels cost 1.
+    Palette {
+        colors: Vec<(Rgb<u8>, u32)>,
+        tolerance: u8,
+    },
+}
+
+// methods
+impl Terrain {
+    /// Cost of stepping onto `px`.
+    pub fn cost(&self, px: Rgb<u8>) -> u32 {
+        match self {
+            Terrain::Uniform => 1,
+            Terrain::Grayscale { max } => {
+                let darkness = u64::from(255 - luminance(px));
+                1 + (darkness * u64::from(max.saturating_sub(1)) / 255) as u32
+            }
+            Terrain::Palette { colors, tolerance } => colors
+                .iter()
+                .find(|&&(color, _)| matches_color(px, color, *tolerance))
+                .map_or(1, |&(_, cost)| cost.max(1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::node::Position;
+    use crate::maze::{Maze, MazeOptions};
+    use crate::pathfinder;
+    use image::RgbImage;
+
+    const MUD: Rgb<u8> = Rgb {
+        data: [139, 69, 19],
+    };
+
+    fn gray(v: u8) -> Rgb<u8> {
+        Rgb { data: [v, v, v] }
+    }
+
+    #[test]
+    fn grayscale_scales_darkness() {
+        let terrain = Terrain::Grayscale { max: 9 };
+        assert_eq!(terrain.cost(gray(255)), 1);
+        assert_eq!(terrain.cost(gray(0)), 9);
+        assert_eq!(terrain.cost(gray(128)), 4);
+        // a max below 1 still costs 1
+        assert_eq!(Terrain::Grayscale { max: 0 }.cost(gray(0)), 1);
+    }
+
+    #[test]
+    fn palette_colors_and_tolerance() {
+        let terrain = Terrain::Palette {
+            colors: vec![(MUD, 20), (gray(200), 0)],
+            tolerance: 10,
+        };
+        assert_eq!(terrain.cost(MUD), 20);
+        assert_eq!(
+            terrain.cost(Rgb {
+                data: [145, 60, 25]
+            }),
+            20
+        );
+        assert_eq!(
+            terrain.cost(Rgb {
+                data: [150, 69, 19]
+            }),
+            1
+        );
+        assert_eq!(terrain.cost(gray(255)), 1);
+        // free terrain is clamped to 1
+        assert_eq!(terrain.cost(gray(200)), 1);
+        assert_eq!(Terrain::Uniform.cost(MUD), 1);
+    }
+
+    #[test]
+    fn cheaper_route_over_shorter() {
+        let rows = [
+            "#.#####", "#.....#", "#m###.#", "#m###.#", "#.....#", "#.#####",
+        ];
+        let img = RgbImage::from_fn(7, 6, |x, y| match rows[y as usize].as_bytes()[x as usize] {
+            b'#' => gray(0),
+            b'm' => MUD,
+            _ => gray(255),
+        });
+        let options = MazeOptions {
+            terrain: Terrain::Palette {
+                colors: vec![(MUD, 20)],
+                tolerance: 0,
+            },
+            ..MazeOptions::default()
+        };
+        let maze = Maze::with_options(&img, &options).unwrap();
+        // straight through the mud
+        assert_eq!(pathfinder::bfs(&maze).cost, 43);
+        // around it
+        for result in &[pathfinder::dijk(&maze), pathfinder::astar(&maze)] {
+            assert_eq!(result.cost, 13);
+            assert!(result.path.as_ref().unwrap().contains(&Position::new(5, 1)));
+        }
+    }
+}
@@ -0,0 +1,243 @@
+//! Mazes drawn as plain text.
+//!
+//! Each character is one cell: `#` is a wall, a space or `.` is a path, and
+//! `S` and `E` mark the start and the exit. There may be several `E`, in
+//! which case reaching any of them solves the maze. Without markers the start
+//! and exit are the first two openings in the border, as for images. Short lines
+//! are padded with walls.
+//!
+//! ```text
+//! #S#####
+//! #   # #
+//! # # # #
+//! # #   #
+//! #####E#
+//! ```
+
+use image::{Rgb, RgbImage};
+
+use super::errors::MazeError;
+use super::node::Position;
+use super::{Endpoint, Maze, MazeOptions};
+
+const WALL: Rgb<u8> = Rgb { data: [0, 0, 0] };
+const PATH: Rgb<u8> = Rgb {
+    data: [255, 255, 255],
+};
+
+/// A text maze converted to pixels, plus any start and exit markers.
+#[derive(Debug, Clone)]
+pub struct TextMaze {
+    image: RgbImage,
+    start: Option<Position>,
+    ends: Vec<Position>,
+}
+
+// associate functions
+impl TextMaze {
+    /// Reads a maze in the text format described in the [module](self) docs.
+    pub fn parse(text: &str) -> Result<TextMaze, MazeError> {
+        let mut lines: Vec<&str> = text.lines().collect();
+        while lines.last() == Some(&"") {
+            lines.pop();
+        }
+        let height = lines.len() as u32;
+        let width = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0) as u32;
+
+        let mut image = RgbImage::from_pixel(width, height, WALL);
+        let mut start = None;
+        let mut ends = Vec::new();
+
+        for (y, line) in lines.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                let pos = Position::new(x as u32, y as u32);
+                match c {
+                    '#' => continue,
+                    ' ' | '.' => {}
+                    'S' => {
+                        if start.is_some() {
+                            return Err(MazeError::DuplicateMarkerError(c, pos));
+                        }
+                        start = Some(pos);
+                    }
+                    'E' => ends.push(pos),
+                    _ => return Err(MazeError::UnknownCharError(c, pos)),
+                }
+                image[pos.as_tuple()] = PATH;
+            }
+        }
+
+        Ok(TextMaze { image, start, ends })
+    }
+}
+
+// methods
+impl TextMaze {
+    /// The maze as a black and white image, one pixel per character.
+    pub fn image(&self) -> &RgbImage {
+        &self.image
+    }
+
+    pub fn into_image(self) -> RgbImage {
+        self.image
+    }
+
+    /// Position of the `S` marker.
+    pub fn start(&self) -> Option<Position> {
+        self.start
+    }
+
+    /// Positions of the `E` markers, in reading order.
+    pub fn ends(&self) -> &[Position] {
+        &self.ends
+    }
+
+    /// Options placing the start and exits on the `S` and `E` markers.
+    pub fn options(&self) -> MazeOptions {
+        let ends = if self.ends.is_empty() {
+            vec![Endpoint::Opening]
+        } else {
+            self.ends.iter().copied().map(Endpoint::At).collect()
+        };
+        MazeOptions {
+            start: self.start.map_or(Endpoint::Opening, Endpoint::At),
+            ends,
+            ..MazeOptions::default()
+        }
+    }
+
+    /// Builds the maze graph, honouring the `S` and `E` markers.
+    pub fn to_maze(&self) -> Result<Maze, MazeError> {
+        Maze::with_options(&self.image, &self.options())
+    }
+}
+
+/// Draws `img` as text with `maze_path` marked by `*`, the reverse of
+/// [`TextMaze::parse`]. Black pixels become `#`, all others a space, and the
+/// ends of the path are drawn as `S` and `E`.
+pub fn write_solution(img: &RgbImage, maze_path: &[Position]) -> String {
+    let width = img.width() as usize;
+    let mut grid: Vec<Vec<char>> = (0..img.height())
+        .map(|y| {
+            (0..img.width())
+                .map(|x| if img[(x, y)] == WALL { '#' } else { ' ' })
+                .collect()
+        })
+        .collect();
+
+    for w in maze_path.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        // diagonal steps join neighboring cells, so marking the ends is enough
+        if a.row() != b.row() && a.col() != b.col() {
+            for p in &[a, b] {
+                grid[p.row() as usize][p.col() as usize] = '*';
+            }
+            continue;
+        }
+        for y in u32::min(a.row(), b.row())..=u32::max(a.row(), b.row()) {
+            for x in u32::min(a.col(), b.col())..=u32::max(a.col(), b.col()) {
+                grid[y as usize][x as usize] = '*';
+            }
+        }
+    }
+    if let (Some(first), Some(last)) = (maze_path.first(), maze_path.last()) {
+        grid[first.row() as usize][first.col() as usize] = 'S';
+        grid[last.row() as usize][last.col() as usize] = 'E';
+    }
+
+    let mut out = String::with_capacity((width + 1) * grid.len());
+    for row in grid {
+        out.extend(row);
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::{scale, MazeOptions};
+    use crate::pathfinder;
+
+    const MAZE: &str = "#S#####\n#   # #\n# # # #\n# #   #\n#####E#\n";
+
+    #[test]
+    fn markers_and_walls() {
+        let tm = TextMaze::parse(MAZE).unwrap();
+        assert_eq!(tm.image().dimensions(), (7, 5));
+        assert_eq!(tm.start(), Some(Position::new(1, 0)));
+        assert_eq!(tm.ends(), &[Position::new(5, 4)]);
+        assert_eq!(tm.image()[(0, 0)], WALL);
+        assert_eq!(tm.image()[(1, 0)], PATH);
+        assert_eq!(tm.image()[(2, 1)], PATH);
+    }
+
+    #[test]
+    fn short_lines_are_padded_with_walls() {
+        let tm = TextMaze::parse("#.###\n#.\n#.###\n").unwrap();
+        assert_eq!(tm.image().dimensions(), (5, 3));
+        assert_eq!(tm.image()[(4, 1)], WALL);
+        assert_eq!(tm.start(), None);
+    }
+
+    #[test]
+    fn bad_characters_and_markers() {
+        match TextMaze::parse("#S#\n#x#\n") {
+            Err(MazeError::UnknownCharError('x', pos)) => assert_eq!(pos, Position::new(1, 1)),
+            other => panic!("unexpected {:?}", other.map(|tm| tm.start())),
+        }
+        assert!(matches!(
+            TextMaze::parse("#S#\n#S#\n"),
+            Err(MazeError::DuplicateMarkerError('S', _))
+        ));
+    }
+
+    #[test]
+    fn solution_round_trip() {
+        let tm = TextMaze::parse(MAZE).unwrap();
+        let maze = tm.to_maze().unwrap();
+        let path = pathfinder::bfs(&maze).path.unwrap();
+        let solved = "\
+#S#####
+#***# #
+# #*# #
+# #***#
+#####E#
+";
+        assert_eq!(write_solution(tm.image(), &path), solved);
+        // the solution reads back as the same maze
+        let again = TextMaze::parse(&solved.replace('*', " ")).unwrap();
+        assert_eq!(again.into_image().into_raw(), tm.into_image().into_raw());
+    }
+
+    #[test]
+    fn scaled_solution_stays_in_the_corridors() {
+        // every character drawn as a 2x2 block
+        let tm = TextMaze::parse(
+            "##  ######\n##  ######\n##      ##\n##      ##\n######  ##\n######  ##\n",
+        )
+        .unwrap();
+        let cells = scale::downsample(tm.image(), 2);
+        let maze = Maze::with_options(&cells, &MazeOptions::default()).unwrap();
+        let path = pathfinder::bfs(&maze).path.unwrap();
+        let solved = "\
+##  ######
+## S######
+## *    ##
+## *****##
+###### *##
+###### E##
+";
+        assert_eq!(
+            write_solution(tm.image(), &scale::upscale_path(&path, 2)),
+            solved
+        );
+    }
+
+    #[test]
+    fn openings_without_markers() {
+        let maze = Maze::from_text("# ###\n#   #\n### #\n").unwrap();
+        assert_eq!(maze.start().position(), Position::new(1, 0));
+        assert_eq!(maze.end().position(), Position::new(3, 2));
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use image::RgbImage;
+
+use super::errors::MazeError;
+use super::keys::Keys;
+use super::node::Position;
+use super::{Connectivity, Endpoint, Grid, MazeOptions};
+
+/// Checks `img` for everything that makes a poor maze and returns one error
+/// per problem, or nothing if the maze is sound.
+///
+/// Besides the errors [`Maze::with_options`](super::Maze::with_options)
+/// would return, this finds images too small to hold a maze, openings in the
+/// border other than the start and exits, and open regions the start cannot
+/// reach, following portals and other links. Doors count as open.
+pub fn validate(img: &RgbImage, options: &MazeOptions) -> Vec<MazeError> {
+    let (width, height) = img.dimensions();
+    let grid = Grid { img, options };
+    let mut errors = Vec::new();
+
+    if width < 3 || height < 3 {
+        errors.push(MazeError::TooSmallError(width, height));
+        if width == 0 || height == 0 {
+            return errors;
+        }
+    }
+    if options.keys.pairs.len() > Keys::MAX {
+        errors.push(MazeError::TooManyKeysError(options.keys.pairs.len()));
+    }
+
+    let mut links: HashMap<Position, Vec<Position>> = HashMap::new();
+    let mut link = |a: Position, b: Position| {
+        links.entry(a).or_default().push(b);
+        links.entry(b).or_default().push(a);
+    };
+    let colors = &options.portals.colors;
+    for (&color, found) in colors.iter().zip(options.portals.find(img)) {
+        match found[..] {
+            [a, b] => link(a, b),
+            _ => errors.push(MazeError::PortalPairError(color, found)),
+        }
+    }
+    for l in &options.links {
+        link(l.a, l.b);
+    }
+
+    let openings = grid.openings();
+    let placed = grid.place_endpoints(&openings);
+    if placed[0].is_empty() {
+        errors.push(MazeError::NoStartError);
+    }
+    if placed[1..].iter().any(Vec::is_empty) {
+        errors.push(MazeError::NoExitError);
+    }
+
+    let mut endpoints = Vec::new();
+    for &pos in placed.iter().flatten() {
+        if pos.col() >= width || pos.row() >= height {
+            errors.push(MazeError::OutOfBoundsError(pos));
+        } else if !grid.open(pos.col(), pos.row()) {
+            errors.push(MazeError::WallEndpointError(pos));
+        } else {
+            endpoints.push(pos);
+        }
+    }
+
+    // with openings picked by order any other opening is a rival entrance,
+    // otherwise it is a hole in the border
+    let picked = Some(&options.start)
+        .into_iter()
+        .chain(&options.ends)
+        .any(|e| *e == Endpoint::Opening);
+    for &pos in &openings {
+        if !placed.iter().flatten().any(|&p| p == pos) {
+            errors.push(if picked {
+                MazeError::ExtraEntranceError(pos)
+            } else {
+                MazeError::BorderGapError(pos)
+            });
+        }
+    }
+
+    let start = match placed[0].first() {
+        Some(start) if endpoints.contains(start) => *start,
+        _ => return errors,
+    };
+    let index = |pos: Position| pos.row() as usize * width as usize + pos.col() as usize;
+    let mut reached = vec![false; width as usize * height as usize];
+    flood(&grid, &links, start, &mut reached);
+
+    for &pos in placed[1..].iter().flatten() {
+        if endpoints.contains(&pos) && !reached[index(pos)] {
+            errors.push(MazeError::UnreachableExitError(pos));
+        }
+    }
+    for y in 0..height {
+        for x in 0..width {
+            let pos = Position::new(x, y);
+            if grid.open(x, y) && !reached[index(pos)] {
+                errors.push(MazeError::UnreachableRegionError(pos));
+                flood(&grid, &links, pos, &mut reached);
+            }
+        }
+    }
+
+    errors
+}
+
+/// Marks every open pixel connected to `from` in `reached`, which holds one
+/// flag per pixel in row order. Linked pixels connect to each other, and so
+/// do diagonal neighbors on 8-connected mazes. One-way pixels are only
+/// crossed their way.
+fn flood(
+    grid: &Grid,
+    links: &HashMap<Position, Vec<Position>>,
+    from: Position,
+    reached: &mut [bool],
+) {
+    let width = grid.img.width() as usize;
+    let mut stack = vec![from];
+    reached[from.row() as usize * width + from.col() as usize] = true;
+
+    while let Some(pos) = stack.pop() {
+        let (x, y) = pos.as_tuple();
+        let (left, up) = (x.wrapping_sub(1), y.wrapping_sub(1));
+        let diagonal = |nx: u32, ny: u32| match grid.options.connectivity {
+            Connectivity::Four => None,
+            Connectivity::Eight { corner_cutting } => {
+                if corner_cutting || (grid.open(nx, y) && grid.open(x, ny)) {
+                    Some((nx, ny))
+                } else {
+                    None
+                }
+            }
+        };
+        let steps = [
+            Some((x, up)),
+            Some((x + 1, y)),
+            Some((x, y + 1)),
+            Some((left, y)),
+            diagonal(x + 1, up),
+            diagonal(x + 1, y + 1),
+            diagonal(left, y + 1),
+            diagonal(left, up),
+        ];
+        let jumps = if links.is_empty() {
+            None
+        } else {
+            links.get(&pos)
+        };
+        let jumps = jumps
+            .into_iter()
+            .flatten()
+            .map(|p| p.as_tuple())
+            .filter(|&(nx, ny)| grid.open(nx, ny));
+        // wrapped coordinates fall outside the image and count as walls
+        let steps = steps
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|&(nx, ny)| grid.open(nx, ny) && grid.can_step(pos, Position::new(nx, ny)));
+
+        for (nx, ny) in steps.chain(jumps) {
+            let i = ny as usize * width + nx as usize;
+            if !reached[i] {
+                reached[i] = true;
+                stack.push(Position::new(nx, ny));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::text::TextMaze;
+
+    /// The problems of a text maze, placing its start and exits as
+    /// [`Maze::from_text`](crate::maze::Maze::from_text) does.
+    fn problems(text: &str) -> Vec<MazeError> {
+        let tm = TextMaze::parse(text).unwrap();
+        validate(tm.image(), &tm.options())
+    }
+
+    #[test]
+    fn sound_maze() {
+        assert!(problems("#S###\n#   #\n### #\n###E#\n").is_empty());
+        assert!(problems("# ###\n#   #\n### #\n").is_empty());
+    }
+
+    #[test]
+    fn too_small() {
+        let errors = problems("# #\n# #\n");
+        assert!(matches!(errors[..], [MazeError::TooSmallError(3, 2)]));
+    }
+
+    #[test]
+    fn missing_exit() {
+        let errors = problems("# ###\n#   #\n#####\n");
+        assert!(matches!(errors[..], [MazeError::NoExitError]));
+    }
+
+    #[test]
+    fn extra_entrance() {
+        let errors = problems("# ###\n#    \n### #\n");
+        match errors[..] {
+            // the right column comes before the bottom row, so it is the exit
+            [MazeError::ExtraEntranceError(pos)] => assert_eq!(pos, Position::new(3, 2)),
+            _ => panic!("unexpected {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn border_gap_with_markers() {
+        let errors = problems("#S###\n#   #\n### #\n# #E#\n");
+        match errors[..] {
+            [MazeError::BorderGapError(gap), MazeError::UnreachableRegionError(region)] => {
+                assert_eq!(gap, Position::new(1, 3));
+                assert_eq!(region, Position::new(1, 3));
+            }
+            _ => panic!("unexpected {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn unreachable_exit_and_region() {
+        let errors = problems("#S#####\n# # # #\n#####E#\n");
+        match errors[..] {
+            [MazeError::UnreachableExitError(exit), MazeError::UnreachableRegionError(a), MazeError::UnreachableRegionError(b)] =>
+            {
+                assert_eq!(exit, Position::new(5, 2));
+                // the region holding the exit is cut off too
+                assert_eq!((a, b), (Position::new(3, 1), Position::new(5, 1)));
+            }
+            _ => panic!("unexpected {:?}", errors),
+        }
+    }
+}
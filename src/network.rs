@@ -0,0 +1,284 @@
+//! Graphs loaded from files instead of drawn as images, such as road
+//! networks.
+//!
+//! A [`Network`] holds named nodes joined by weighted, directed edges and is
+//! searched by the same [`Solver`](crate::algos::Solver)s as a maze. It is
+//! read from DIMACS shortest path files, CSV edge lists or a simple subset of
+//! Graphviz DOT. When every node has coordinates, A* is guided by the
+//! distance between them to the nearest exit.
+
+mod csv;
+mod dimacs;
+mod dot;
+pub mod errors;
+
+use std::collections::HashMap;
+
+use crate::maze::node::NodeIndex;
+use crate::pathfinder::{Edges, Graph, Heuristic};
+
+use errors::NetworkError;
+
+/// A graph of named nodes joined by weighted, directed edges. Nodes have no
+/// pixel position, so the [`path`](crate::pathfinder::SolveResult::path) of
+/// a search is empty and its [`route`](crate::pathfinder::SolveResult::route)
+/// names the nodes. The search runs from the first node in the file to the
+/// last unless [`set_start`](Network::set_start) and
+/// [`set_ends`](Network::set_ends) pick others.
+#[derive(Debug, Clone, Default)]
+pub struct Network {
+    // the edges leaving each node, with their costs
+    adjacency: Vec<Vec<(NodeIndex, u32)>>,
+    names: Vec<String>,
+    index: HashMap<String, NodeIndex>,
+    coordinates: Vec<Option<(f64, f64)>>,
+    edges: usize,
+    start: NodeIndex,
+    ends: Vec<NodeIndex>,
+}
+
+// associate functions
+impl Network {
+    /// Reads a graph in the format of the 9th DIMACS implementation
+    /// challenge: arcs `a u v w` from the `.gr` file in `gr` and, if given,
+    /// node coordinates `v id x y` from the `.co` file in `co`. Nodes are
+    /// named by their number.
+    pub fn from_dimacs(gr: &str, co: Option<&str>) -> Result<Network, NetworkError> {
+        dimacs::parse(gr, co)
+    }
+
+    /// Reads an edge list with one `source,target[,weight]` row per edge. The
+    /// weight defaults to 1. Blank lines and lines starting with `#` are
+    /// skipped. The first row is a header if its weight is not a number, or
+    /// if it has no weight and names its columns with words like `source`
+    /// and `target`. Each edge may be taken both ways unless `directed` is set.
+    pub fn from_csv(text: &str, directed: bool) -> Result<Network, NetworkError> {
+        csv::parse(text, directed)
+    }
+
+    /// Reads a `graph` or `digraph` in the Graphviz DOT language. Edges take
+    /// their cost from a `weight` attribute, 1 by default, and nodes their
+    /// coordinates from a `pos="x,y"` attribute. Subgraphs and ports are not
+    /// supported.
+    pub fn from_dot(text: &str) -> Result<Network, NetworkError> {
+        dot::parse(text)
+    }
+}
+
+// methods
+impl Network {
+    /// Number of nodes in the graph.
+    pub fn count(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    /// Number of directed edges, counting an edge that may be taken both ways
+    /// twice.
+    pub fn edge_count(&self) -> usize {
+        self.edges
+    }
+
+    /// The edges leaving the node at `index`, each with its cost.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` does not belong to this network.
+    pub fn neighbors(&self, index: NodeIndex) -> &[(NodeIndex, u32)] {
+        &self.adjacency[index as usize]
+    }
+
+    /// The name the file gives the node at `index`.
+    pub fn name(&self, index: NodeIndex) -> &str {
+        &self.names[index as usize]
+    }
+
+    /// The index of the node called `name`.
+    pub fn index_of(&self, name: &str) -> Option<NodeIndex> {
+        self.index.get(name).copied()
+    }
+
+    /// The coordinates of the node at `index`, if the file gives them.
+    pub fn coordinates(&self, index: NodeIndex) -> Option<(f64, f64)> {
+        self.coordinates[index as usize]
+    }
+
+    /// Whether every node has coordinates, which A* needs to estimate
+    /// distances.
+    pub fn has_coordinates(&self) -> bool {
+        self.coordinates.iter().all(Option::is_some)
+    }
+
+    pub fn start_index(&self) -> NodeIndex {
+        self.start
+    }
+
+    /// Every exit, in the order they were given. There is always at least
+    /// one.
+    pub fn ends(&self) -> &[NodeIndex] {
+        &self.ends
+    }
+
+    /// Starts the search from the node called `name`.
+    pub fn set_start(&mut self, name: &str) -> Result<(), NetworkError> {
+        self.start = self.lookup(name)?;
+        Ok(())
+    }
+
+    /// Ends the search at whichever of the nodes called `names` is reached
+    /// first. Keeps the current exits if `names` is empty.
+    pub fn set_ends(&mut self, names: &[String]) -> Result<(), NetworkError> {
+        if !names.is_empty() {
+            self.ends = names
+                .iter()
+                .map(|name| self.lookup(name))
+                .collect::<Result<_, _>>()?;
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Result<NodeIndex, NetworkError> {
+        self.index_of(name)
+            .ok_or_else(|| NetworkError::UnknownNodeError(name.to_owned()))
+    }
+
+    /// The node called `name`, added if it is new.
+    fn add_node(&mut self, name: &str) -> NodeIndex {
+        if let Some(index) = self.index_of(name) {
+            return index;
+        }
+        let index = self.adjacency.len() as NodeIndex;
+        self.adjacency.push(Vec::new());
+        self.names.push(name.to_owned());
+        self.coordinates.push(None);
+        self.index.insert(name.to_owned(), index);
+        index
+    }
+
+    /// Adds an edge from `a` to `b`, and back if `both_ways`.
+    fn add_edge(&mut self, a: NodeIndex, b: NodeIndex, cost: u32, both_ways: bool) {
+        self.adjacency[a as usize].push((b, cost));
+        self.edges += 1;
+        if both_ways {
+            self.adjacency[b as usize].push((a, cost));
+            self.edges += 1;
+        }
+    }
+
+    fn set_coordinates(&mut self, index: NodeIndex, x: f64, y: f64) {
+        self.coordinates[index as usize] = Some((x, y));
+    }
+
+    /// Checks the network is not empty and sets the default start and exit.
+    fn finish(mut self) -> Result<Network, NetworkError> {
+        if self.adjacency.is_empty() {
+            return Err(NetworkError::EmptyError);
+        }
+        self.start = 0;
+        self.ends = vec![self.adjacency.len() as NodeIndex - 1];
+        Ok(self)
+    }
+
+    /// Distance of the kind `heuristic` measures between the nodes at `a`
+    /// and `b`, which must both have coordinates. Edges run in any
+    /// direction, so [`Octile`](Heuristic::Octile) measures the straight
+    /// line.
+    fn distance(&self, heuristic: Heuristic, a: NodeIndex, b: NodeIndex) -> f64 {
+        match (heuristic, self.coordinates(a), self.coordinates(b)) {
+            (Heuristic::Octile, Some((ax, ay)), Some((bx, by))) => (ax - bx).hypot(ay - by),
+            (Heuristic::Manhattan, Some((ax, ay)), Some((bx, by))) => {
+                (ax - bx).abs() + (ay - by).abs()
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+impl Graph for Network {
+    fn count(&self) -> usize {
+        Network::count(self)
+    }
+
+    fn edges(&self, index: NodeIndex) -> Edges<'_> {
+        Edges::from_links(self.neighbors(index))
+    }
+
+    fn start_index(&self) -> NodeIndex {
+        self.start
+    }
+
+    fn ends(&self) -> &[NodeIndex] {
+        &self.ends
+    }
+
+    /// The distance `heuristic` measures to the nearest exit, scaled by the
+    /// lowest cost per unit of distance of any edge so it never
+    /// overestimates. Zero without coordinates or for [`Heuristic::Zero`].
+    fn estimator(&self, heuristic: Heuristic) -> Box<dyn Fn(NodeIndex) -> i64 + '_> {
+        if heuristic == Heuristic::Zero || !self.has_coordinates() {
+            return Box::new(|_| 0);
+        }
+        let scale = (0..self.count() as NodeIndex)
+            .flat_map(|a| self.neighbors(a).iter().map(move |&(b, cost)| (a, b, cost)))
+            .map(|(a, b, cost)| (f64::from(cost), self.distance(heuristic, a, b)))
+            .filter(|&(_, d)| d > 0.0)
+            .map(|(cost, d)| cost / d)
+            .fold(f64::INFINITY, f64::min);
+        if !scale.is_finite() {
+            return Box::new(|_| 0);
+        }
+        // shaved so rounding never lifts the estimate above the true cost
+        let scale = scale * (1.0 - 1e-9);
+        Box::new(move |index: NodeIndex| {
+            let nearest = self
+                .ends
+                .iter()
+                .map(|&e| self.distance(heuristic, index, e))
+                .fold(f64::INFINITY, f64::min);
+            (scale * nearest).floor() as i64
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinder;
+
+    /// A triangle where the long way round is cheaper than the direct edge.
+    const TRIANGLE: &str = "graph {
+        a [pos=\"0,0\"]
+        b [pos=\"3,4\"]
+        c [pos=\"6,0\"]
+        a -- b [weight=5]
+        b -- c [weight=5]
+        a -- c [weight=20]
+    }";
+
+    #[test]
+    fn estimates_follow_the_heuristic() {
+        let network = Network::from_dot(TRIANGLE).unwrap();
+        // the straight line from a to c is 6 long and the cheapest edge
+        // costs 1 per unit of it, but 5/7 per unit of Manhattan distance
+        let estimate = |heuristic| network.estimator(heuristic)(0);
+        assert_eq!(estimate(Heuristic::Octile), 5);
+        assert_eq!(estimate(Heuristic::Manhattan), 4);
+        assert_eq!(estimate(Heuristic::Zero), 0);
+        for &heuristic in &[Heuristic::Octile, Heuristic::Manhattan, Heuristic::Zero] {
+            assert_eq!(
+                pathfinder::astar_with_heuristic(&network, heuristic).cost,
+                10
+            );
+        }
+    }
+
+    #[test]
+    fn results_hold_nodes_not_positions() {
+        let network = Network::from_dot(TRIANGLE).unwrap();
+        let result = pathfinder::dijk(&network);
+        assert_eq!(result.route, vec![0, 1, 2]);
+        assert_eq!(result.path, Some(Vec::new()));
+        assert!(result.jumps.is_empty());
+        assert_eq!(result.visited, vec![0, 1, 2]);
+        assert_eq!(network.edges(0).collect::<Vec<_>>(), vec![(1, 5), (2, 20)]);
+    }
+}
@@ -0,0 +1,123 @@
+//! Edge lists as comma separated values.
+
+use super::errors::NetworkError;
+use super::Network;
+
+/// Column names that mark a first row without a weight as a header.
+const HEADER_WORDS: &[&str] = &[
+    "source", "target", "from", "to", "src", "dst", "start", "end", "node", "weight", "cost",
+];
+
+pub(super) fn parse(text: &str, directed: bool) -> Result<Network, NetworkError> {
+    let mut network = Network::default();
+    let mut first = true;
+
+    for (i, line) in text.lines().enumerate() {
+        let err = |msg: &str| NetworkError::ParseError(i + 1, msg.to_owned());
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line
+            .split(',')
+            .map(|f| f.trim().trim_matches('"'))
+            .collect();
+        let (source, target, weight) = match fields[..] {
+            [source, target] => (source, target, None),
+            [source, target, weight, ..] => (source, target, Some(weight)),
+            _ => return Err(err("expected source,target[,weight]")),
+        };
+        if std::mem::replace(&mut first, false) && is_header(source, target, weight) {
+            continue;
+        }
+        let cost = match weight.map(str::parse) {
+            None => 1,
+            Some(Ok(cost)) => cost,
+            Some(Err(_)) => return Err(err("weight must be a non-negative integer")),
+        };
+        let a = network.add_node(source);
+        let b = network.add_node(target);
+        network.add_edge(a, b, cost, !directed);
+    }
+
+    network.finish()
+}
+
+/// Whether the first row names its columns: its weight is not a number, or
+/// it has no weight and both names are header words like `source` and
+/// `target`.
+fn is_header(source: &str, target: &str, weight: Option<&str>) -> bool {
+    match weight {
+        Some(weight) => weight.parse::<f64>().is_err(),
+        None => [source, target]
+            .iter()
+            .all(|name| HEADER_WORDS.contains(&name.to_ascii_lowercase().as_str())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinder::Graph;
+
+    #[test]
+    fn weighted_edges() {
+        let network = parse("a,b,3\nb,c,4\n", false).unwrap();
+        assert_eq!(network.count(), 3);
+        assert_eq!(network.edge_count(), 4);
+        let (a, b) = (
+            network.index_of("a").unwrap(),
+            network.index_of("b").unwrap(),
+        );
+        assert_eq!(network.cost_to(a, b), Some(3));
+        assert_eq!(network.cost_to(b, a), Some(3));
+    }
+
+    #[test]
+    fn directed_edges() {
+        let network = parse("a,b\n", true).unwrap();
+        let (a, b) = (
+            network.index_of("a").unwrap(),
+            network.index_of("b").unwrap(),
+        );
+        assert_eq!(network.cost_to(a, b), Some(1));
+        assert_eq!(network.cost_to(b, a), None);
+    }
+
+    #[test]
+    fn three_column_header() {
+        let network = parse("from,to,cost\na,b,2\n", false).unwrap();
+        assert_eq!(network.count(), 2);
+        assert_eq!(network.index_of("from"), None);
+    }
+
+    #[test]
+    fn two_column_header() {
+        let network = parse("source,target\na,b\nb,c\n", false).unwrap();
+        assert_eq!(network.count(), 3);
+        assert_eq!(network.index_of("source"), None);
+        assert_eq!(network.name(network.start_index()), "a");
+    }
+
+    #[test]
+    fn header_after_comment() {
+        let text = "# road network\n\nsource,target,weight\na,b,2\n";
+        let network = parse(text, false).unwrap();
+        assert_eq!(network.count(), 2);
+        assert_eq!(network.name(network.start_index()), "a");
+    }
+
+    #[test]
+    fn only_first_row_is_a_header() {
+        match parse("a,b,1\nc,d,x\n", false) {
+            Err(NetworkError::ParseError(2, _)) => {}
+            other => panic!("expected a parse error on line 2, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn names_are_not_headers() {
+        let network = parse("a,b\n", false).unwrap();
+        assert_eq!(network.count(), 2);
+    }
+}
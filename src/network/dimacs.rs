@@ -0,0 +1,95 @@
+//! DIMACS shortest path files: `.gr` arcs and `.co` coordinates.
+
+use super::errors::NetworkError;
+use super::Network;
+
+pub(super) fn parse(gr: &str, co: Option<&str>) -> Result<Network, NetworkError> {
+    let mut network = Network::default();
+    let mut declared = None;
+
+    for (i, line) in gr.lines().enumerate() {
+        let err = |msg: &str| NetworkError::ParseError(i + 1, msg.to_owned());
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields[..] {
+            [] | ["c", ..] => {}
+            ["p", "sp", n, _] => {
+                let n: u32 = n.parse().map_err(|_| err("bad node count"))?;
+                // nodes are numbered from 1, in index order
+                for id in 1..=n {
+                    network.add_node(&id.to_string());
+                }
+                declared = Some(n);
+            }
+            ["a", u, v, w] => {
+                let n = declared.ok_or_else(|| err("arc before the problem line"))?;
+                let node = |id: &str| match id.parse::<u32>() {
+                    Ok(id) if id >= 1 && id <= n => Ok(id - 1),
+                    _ => Err(err(&format!("no node {}", id))),
+                };
+                let cost = w.parse().map_err(|_| err("bad arc length"))?;
+                network.add_edge(node(u)?, node(v)?, cost, false);
+            }
+            _ => return Err(err("expected a comment, problem line or arc")),
+        }
+    }
+
+    if let Some(co) = co {
+        for (i, line) in co.lines().enumerate() {
+            let err = |msg: &str| NetworkError::ParseError(i + 1, msg.to_owned());
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                [] | ["c", ..] | ["p", ..] => {}
+                ["v", id, x, y] => {
+                    let index = network
+                        .index_of(id)
+                        .ok_or_else(|| err(&format!("no node {}", id)))?;
+                    let x = x.parse().map_err(|_| err("bad x coordinate"))?;
+                    let y = y.parse().map_err(|_| err("bad y coordinate"))?;
+                    network.set_coordinates(index, x, y);
+                }
+                _ => return Err(err("expected a comment, problem line or node")),
+            }
+        }
+    }
+
+    network.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinder::Graph;
+
+    const GR: &str = "c a triangle\np sp 3 3\na 1 2 5\na 2 3 6\na 1 3 20\n";
+
+    #[test]
+    fn arcs_go_one_way() {
+        let network = parse(GR, None).unwrap();
+        assert_eq!(network.count(), 3);
+        assert_eq!(network.name(0), "1");
+        assert_eq!(network.cost_to(0, 1), Some(5));
+        assert_eq!(network.cost_to(1, 0), None);
+        assert!(!network.has_coordinates());
+    }
+
+    #[test]
+    fn coordinates_from_co() {
+        let co = "c coordinates\np aux sp co 3\nv 1 0 0\nv 2 10 0\nv 3 10 -5\n";
+        let network = parse(GR, Some(co)).unwrap();
+        assert!(network.has_coordinates());
+        assert_eq!(network.coordinates(2), Some((10.0, -5.0)));
+    }
+
+    #[test]
+    fn arc_to_an_undeclared_node() {
+        match parse("p sp 2 1\na 1 3 1\n", None) {
+            Err(NetworkError::ParseError(2, msg)) => assert_eq!(msg, "no node 3"),
+            other => panic!("unexpected {:?}", other.map(|n| n.count())),
+        }
+    }
+
+    #[test]
+    fn arc_before_the_problem_line() {
+        assert!(parse("a 1 2 1\np sp 2 1\n", None).is_err());
+    }
+}
@@ -0,0 +1,336 @@
+//! A simple subset of the Graphviz DOT language: one `graph` or `digraph`
+//! made of node and edge statements with attribute lists.
+
+use super::errors::NetworkError;
+use super::Network;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// An identifier, number or quoted string.
+    Id(String),
+    /// `->` or `--`.
+    Edge,
+    /// `{`, `}`, `[`, `]`, `=`, `;`, `,` or `:`.
+    Punct(char),
+}
+
+pub(super) fn parse(text: &str) -> Result<Network, NetworkError> {
+    Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+    }
+    .graph()
+}
+
+/// Splits `text` into tokens, each with the line it is on.
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, NetworkError> {
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            _ if c.is_whitespace() => {}
+            // comments and preprocessor lines
+            '#' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'/') => while chars.next_if(|&c| c != '\n').is_some() {},
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prv = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if prv == '*' => break,
+                        Some(c) => {
+                            line += usize::from(c == '\n');
+                            prv = c;
+                        }
+                        None => return Err(error(line, "unterminated comment")),
+                    }
+                }
+            }
+            '"' => {
+                let start = line;
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if chars.peek() == Some(&'"') => id.push('"'),
+                        Some(c) => {
+                            line += usize::from(c == '\n');
+                            id.push(c);
+                        }
+                        None => return Err(error(start, "unterminated string")),
+                    }
+                }
+                tokens.push((Token::Id(id), start));
+            }
+            '-' if chars.next_if(|&c| c == '>' || c == '-').is_some() => {
+                tokens.push((Token::Edge, line));
+            }
+            '{' | '}' | '[' | ']' | '=' | ';' | ',' | ':' => tokens.push((Token::Punct(c), line)),
+            // a minus sign only starts a number
+            _ if is_id_char(c) || c == '-' => {
+                let mut id = c.to_string();
+                while let Some(c) = chars.next_if(|&c| is_id_char(c)) {
+                    id.push(c);
+                }
+                tokens.push((Token::Id(id), line));
+            }
+            _ => return Err(error(line, &format!("unexpected {}", c))),
+        }
+    }
+    Ok(tokens)
+}
+
+fn is_id_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '.' || !c.is_ascii()
+}
+
+fn error(line: usize, msg: &str) -> NetworkError {
+    NetworkError::ParseError(line, msg.to_owned())
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(t, _)| t)
+    }
+
+    fn line(&self) -> usize {
+        match self.tokens.get(self.pos).or_else(|| self.tokens.last()) {
+            Some(&(_, line)) => line,
+            None => 1,
+        }
+    }
+
+    fn next(&mut self) -> Result<Token, NetworkError> {
+        let token = self
+            .peek()
+            .cloned()
+            .ok_or_else(|| error(self.line(), "unexpected end of file"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), NetworkError> {
+        let line = self.line();
+        match self.next()? {
+            Token::Punct(p) if p == c => Ok(()),
+            _ => Err(error(line, &format!("expected {}", c))),
+        }
+    }
+
+    fn id(&mut self) -> Result<String, NetworkError> {
+        let line = self.line();
+        match self.next()? {
+            Token::Id(id) => Ok(id),
+            _ => Err(error(line, "expected a name")),
+        }
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.peek() == Some(token);
+        self.pos += usize::from(found);
+        found
+    }
+
+    /// `[strict] (graph | digraph) [name] { statements }`
+    fn graph(&mut self) -> Result<Network, NetworkError> {
+        let mut kind = self.id()?.to_lowercase();
+        if kind == "strict" {
+            kind = self.id()?.to_lowercase();
+        }
+        let directed = match kind.as_str() {
+            "graph" => false,
+            "digraph" => true,
+            _ => return Err(error(self.line(), "expected graph or digraph")),
+        };
+        if let Some(Token::Id(_)) = self.peek() {
+            self.pos += 1;
+        }
+        self.expect('{')?;
+
+        let mut network = Network::default();
+        let mut default_weight = 1;
+        loop {
+            let line = self.line();
+            match self.next()? {
+                Token::Punct('}') => break,
+                Token::Punct(';') | Token::Punct(',') => {}
+                Token::Punct('{') => return Err(error(line, "subgraphs are not supported")),
+                Token::Id(id) => {
+                    let keyword = id.to_lowercase();
+                    if keyword == "subgraph" {
+                        return Err(error(line, "subgraphs are not supported"));
+                    }
+                    if self.peek() == Some(&Token::Punct('[')) && is_keyword(&keyword) {
+                        let attrs = self.attributes()?;
+                        if keyword == "edge" {
+                            default_weight = weight(&attrs, 1, line)?;
+                        }
+                    } else if self.eat(&Token::Punct('=')) {
+                        // graph attribute
+                        self.id()?;
+                    } else {
+                        self.statement(&mut network, id, directed, default_weight)?;
+                    }
+                }
+                _ => return Err(error(line, "expected a statement")),
+            }
+        }
+        network.finish()
+    }
+
+    /// A node `a [attributes]` or a chain of edges `a -> b -> c
+    /// [attributes]`, whose first name has been read.
+    fn statement(
+        &mut self,
+        network: &mut Network,
+        first: String,
+        directed: bool,
+        default_weight: u32,
+    ) -> Result<(), NetworkError> {
+        let line = self.line();
+        let mut chain = vec![first];
+        loop {
+            if self.peek() == Some(&Token::Punct(':')) {
+                return Err(error(line, "ports are not supported"));
+            }
+            if !self.eat(&Token::Edge) {
+                break;
+            }
+            if self.peek() == Some(&Token::Punct('{')) {
+                return Err(error(line, "subgraphs are not supported"));
+            }
+            chain.push(self.id()?);
+        }
+        let attrs = if self.peek() == Some(&Token::Punct('[')) {
+            self.attributes()?
+        } else {
+            Vec::new()
+        };
+
+        let nodes: Vec<_> = chain.iter().map(|name| network.add_node(name)).collect();
+        if let [node] = nodes[..] {
+            if let Some((_, pos)) = attrs.iter().find(|(k, _)| k == "pos") {
+                let (x, y) = coordinates(pos).ok_or_else(|| error(line, "bad pos"))?;
+                network.set_coordinates(node, x, y);
+            }
+        }
+        let cost = weight(&attrs, default_weight, line)?;
+        for pair in nodes.windows(2) {
+            network.add_edge(pair[0], pair[1], cost, !directed);
+        }
+        Ok(())
+    }
+
+    /// One or more `[name = value, ...]` lists.
+    fn attributes(&mut self) -> Result<Vec<(String, String)>, NetworkError> {
+        let mut attrs = Vec::new();
+        while self.eat(&Token::Punct('[')) {
+            while !self.eat(&Token::Punct(']')) {
+                let name = self.id()?;
+                self.expect('=')?;
+                attrs.push((name, self.id()?));
+                if !self.eat(&Token::Punct(',')) {
+                    self.eat(&Token::Punct(';'));
+                }
+            }
+        }
+        Ok(attrs)
+    }
+}
+
+fn is_keyword(id: &str) -> bool {
+    id == "graph" || id == "node" || id == "edge"
+}
+
+/// The `weight` in `attrs`, or `default` if there is none.
+fn weight(attrs: &[(String, String)], default: u32, line: usize) -> Result<u32, NetworkError> {
+    match attrs.iter().find(|(k, _)| k == "weight") {
+        Some((_, w)) => w.parse().map_err(|_| {
+            let msg = format!(
+                "weight {} is not supported, weights must be non-negative integers \
+                 (scale fractional weights up)",
+                w
+            );
+            error(line, &msg)
+        }),
+        None => Ok(default),
+    }
+}
+
+/// The `x,y` of a `pos` attribute, which may end in `!`.
+fn coordinates(pos: &str) -> Option<(f64, f64)> {
+    let mut parts = pos.trim_end_matches('!').split(',');
+    let x = parts.next()?.trim().parse().ok()?;
+    let y = parts.next()?.trim().parse().ok()?;
+    Some((x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinder::Graph;
+
+    #[test]
+    fn edge_chains_and_weights() {
+        let network = parse("graph g {\n  a -- b -- c [weight=4];\n  c -- d\n}\n").unwrap();
+        assert_eq!(network.count(), 4);
+        let (a, b, d) = (
+            network.index_of("a").unwrap(),
+            network.index_of("b").unwrap(),
+            network.index_of("d").unwrap(),
+        );
+        assert_eq!(network.cost_to(a, b), Some(4));
+        assert_eq!(network.cost_to(b, a), Some(4));
+        assert_eq!(network.cost_to(d, network.index_of("c").unwrap()), Some(1));
+    }
+
+    #[test]
+    fn digraph_edges_go_one_way() {
+        let network = parse("digraph { a -> b }").unwrap();
+        let (a, b) = (
+            network.index_of("a").unwrap(),
+            network.index_of("b").unwrap(),
+        );
+        assert_eq!(network.cost_to(a, b), Some(1));
+        assert_eq!(network.cost_to(b, a), None);
+    }
+
+    #[test]
+    fn default_edge_weight() {
+        let network = parse("graph { edge [weight=7]; a -- b; c -- d [weight=2] }").unwrap();
+        let index = |name| network.index_of(name).unwrap();
+        assert_eq!(network.cost_to(index("a"), index("b")), Some(7));
+        assert_eq!(network.cost_to(index("c"), index("d")), Some(2));
+    }
+
+    #[test]
+    fn positions_and_comments() {
+        let text =
+            "graph {\n  // nodes\n  a [pos=\"1,2!\"]\n  /* b */ b [pos=\"3.5,4\"]\n  a -- b\n}";
+        let network = parse(text).unwrap();
+        assert!(network.has_coordinates());
+        assert_eq!(network.coordinates(1), Some((3.5, 4.0)));
+    }
+
+    #[test]
+    fn fractional_weight_is_refused() {
+        match parse("graph {\n a -- b [weight=2.5]\n}") {
+            Err(NetworkError::ParseError(2, msg)) => assert!(msg.contains("2.5")),
+            other => panic!("unexpected {:?}", other.map(|n| n.count())),
+        }
+    }
+
+    #[test]
+    fn subgraphs_are_refused() {
+        assert!(parse("graph { subgraph s { a -- b } }").is_err());
+        assert!(parse("graph { a -- { b c } }").is_err());
+    }
+}
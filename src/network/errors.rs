@@ -0,0 +1,27 @@
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+/// Reasons a graph file could not be turned into a
+/// [`Network`](super::Network).
+#[derive(Debug)]
+pub enum NetworkError {
+    /// A line of the file, counted from 1, that could not be understood.
+    ParseError(usize, String),
+    /// A node name that is not in the graph.
+    UnknownNodeError(String),
+    /// The file has no nodes.
+    EmptyError,
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        use NetworkError::*;
+        match self {
+            ParseError(line, msg) => write!(f, "line {}: {}", line, msg),
+            UnknownNodeError(name) => write!(f, "graph has no node named {}", name),
+            EmptyError => write!(f, "graph has no nodes"),
+        }
+    }
+}
+
+impl Error for NetworkError {}
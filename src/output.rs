@@ -0,0 +1,235 @@
+//! Formats and file names of saved solutions.
+
+use std::io::{self, Write};
+
+use image::bmp::BMPEncoder;
+use image::jpeg::JPEGEncoder;
+use image::png::PNGEncoder;
+use image::pnm::{PNMEncoder, PNMSubtype, SampleEncoding};
+use image::RgbImage;
+
+use crate::cli_errors::InvalidTemplateError;
+
+/// Image format of a saved solution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Png,
+    Bmp,
+    Jpeg,
+    Tiff,
+    Ppm,
+    /// Drawn from the maze by [`render::svg`](maze_solver::render::svg)
+    /// instead of encoded from the solved image.
+    Svg,
+    /// The search spreading through the maze, animated by
+    /// [`render::animate`](maze_solver::render::animate).
+    Gif,
+}
+
+// associate functions
+impl Format {
+    /// The format called `name`, which may also be a file extension.
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Some(Format::Png),
+            "bmp" => Some(Format::Bmp),
+            "jpeg" | "jpg" => Some(Format::Jpeg),
+            "tiff" | "tif" => Some(Format::Tiff),
+            "ppm" => Some(Format::Ppm),
+            "svg" => Some(Format::Svg),
+            "gif" => Some(Format::Gif),
+            _ => None,
+        }
+    }
+}
+
+// methods
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Png => "png",
+            Format::Bmp => "bmp",
+            Format::Jpeg => "jpg",
+            Format::Tiff => "tiff",
+            Format::Ppm => "ppm",
+            Format::Svg => "svg",
+            Format::Gif => "gif",
+        }
+    }
+
+    /// Whether this format is drawn from the maze and the search rather than
+    /// from the solved image.
+    pub fn is_drawing(self) -> bool {
+        self == Format::Svg || self == Format::Gif
+    }
+
+    /// Writes `img` to `out` in this format.
+    pub fn encode(self, img: &RgbImage, mut out: &mut dyn Write) -> io::Result<()> {
+        let (width, height) = img.dimensions();
+        match self {
+            Format::Png => PNGEncoder::new(out).encode(img, width, height, image::RGB(8)),
+            Format::Bmp => BMPEncoder::new(&mut out).encode(img, width, height, image::RGB(8)),
+            Format::Jpeg => JPEGEncoder::new(&mut out).encode(img, width, height, image::RGB(8)),
+            Format::Ppm => PNMEncoder::new(out)
+                .with_subtype(PNMSubtype::Pixmap(SampleEncoding::Binary))
+                .encode(&**img, width, height, image::RGB(8)),
+            Format::Tiff => write_tiff(img, out),
+            Format::Svg | Format::Gif => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "svg and gif files are drawn from the maze, not from an image",
+            )),
+        }
+    }
+}
+
+/// Values for the placeholders of an output name template.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Naming<'a> {
+    /// Input file name without its extension.
+    pub stem: &'a str,
+    pub algo: &'a str,
+    /// Nodes on the path.
+    pub len: usize,
+    pub cost: u64,
+    /// Size of the solved image, zero for graph files.
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Fills the `{name}` placeholders of `template` in from `naming`.
+pub fn expand(template: &str, naming: &Naming) -> Result<String, InvalidTemplateError> {
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        name.push_str(&rest[..open]);
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| InvalidTemplateError(template.to_owned()))?;
+        let value = match &rest[open + 1..open + close] {
+            "stem" => naming.stem.to_owned(),
+            "algo" => naming.algo.to_owned(),
+            "len" => naming.len.to_string(),
+            "cost" => naming.cost.to_string(),
+            "w" => naming.width.to_string(),
+            "h" => naming.height.to_string(),
+            _ => return Err(InvalidTemplateError(template.to_owned())),
+        };
+        name.push_str(&value);
+        rest = &rest[open + close + 1..];
+    }
+    name.push_str(rest);
+    Ok(name)
+}
+
+/// Writes `img` as an uncompressed baseline TIFF file, which the image crate
+/// cannot encode.
+fn write_tiff(img: &RgbImage, out: &mut dyn Write) -> io::Result<()> {
+    const SHORT: u16 = 3;
+    const LONG: u16 = 4;
+    const RATIONAL: u16 = 5;
+
+    let (width, height) = img.dimensions();
+    let data: &[u8] = img;
+    let entries: u32 = 12;
+    // header, then the directory, then the values too long to fit in it
+    let ifd = 8;
+    let bits = ifd + 2 + entries * 12 + 4;
+    let resolution = bits + 6;
+    let pixels = resolution + 8;
+
+    let tags: [(u16, u16, u32, u32); 12] = [
+        (256, LONG, 1, width),
+        (257, LONG, 1, height),
+        (258, SHORT, 3, bits),
+        // no compression
+        (259, SHORT, 1, 1),
+        // RGB
+        (262, SHORT, 1, 2),
+        (273, LONG, 1, pixels),
+        (277, SHORT, 1, 3),
+        (278, LONG, 1, height),
+        (279, LONG, 1, data.len() as u32),
+        (282, RATIONAL, 1, resolution),
+        (283, RATIONAL, 1, resolution),
+        // inches
+        (296, SHORT, 1, 2),
+    ];
+
+    out.write_all(b"II*\0")?;
+    out.write_all(&ifd.to_le_bytes())?;
+    out.write_all(&(entries as u16).to_le_bytes())?;
+    for &(tag, kind, count, value) in &tags {
+        out.write_all(&tag.to_le_bytes())?;
+        out.write_all(&kind.to_le_bytes())?;
+        out.write_all(&count.to_le_bytes())?;
+        // a single short is stored in the first two bytes of the value
+        if kind == SHORT && count == 1 {
+            out.write_all(&(value as u16).to_le_bytes())?;
+            out.write_all(&[0, 0])?;
+        } else {
+            out.write_all(&value.to_le_bytes())?;
+        }
+    }
+    // no further directories
+    out.write_all(&0u32.to_le_bytes())?;
+    for _ in 0..3 {
+        out.write_all(&8u16.to_le_bytes())?;
+    }
+    // 72 pixels per inch
+    out.write_all(&72u32.to_le_bytes())?;
+    out.write_all(&1u32.to_le_bytes())?;
+    out.write_all(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naming() -> Naming<'static> {
+        Naming {
+            stem: "braid200",
+            algo: "astar",
+            len: 42,
+            cost: 1234,
+            width: 201,
+            height: 199,
+        }
+    }
+
+    #[test]
+    fn expands_every_placeholder() {
+        let naming = naming();
+        let expand = |template| expand(template, &naming).unwrap();
+        assert_eq!(expand("{stem}"), "braid200");
+        assert_eq!(expand("{algo}"), "astar");
+        assert_eq!(expand("{len}"), "42");
+        assert_eq!(expand("{cost}"), "1234");
+        assert_eq!(expand("{w}x{h}"), "201x199");
+        assert_eq!(expand("{algo}/{stem}-{len}"), "astar/braid200-42");
+        assert_eq!(expand("plain"), "plain");
+    }
+
+    #[test]
+    fn unknown_or_unclosed_placeholders() {
+        for template in &["{size}", "{stem", "a-{}-b"] {
+            match expand(template, &naming()) {
+                Err(InvalidTemplateError(t)) => assert_eq!(&t, template),
+                Ok(name) => panic!("{} expanded to {}", template, name),
+            }
+        }
+    }
+
+    #[test]
+    fn tiff_round_trip() {
+        let img = RgbImage::from_fn(7, 5, |x, y| image::Rgb {
+            data: [(x * 30) as u8, (y * 50) as u8, ((x + y) * 10) as u8],
+        });
+        let mut out = Vec::new();
+        Format::Tiff.encode(&img, &mut out).unwrap();
+        let decoded = image::load_from_memory_with_format(&out, image::ImageFormat::TIFF)
+            .unwrap()
+            .to_rgb();
+        assert_eq!(decoded.dimensions(), (7, 5));
+        assert_eq!(decoded.into_raw(), img.into_raw());
+    }
+}
@@ -1,10 +1,129 @@
+//! Search algorithms over a [`Graph`], such as a [`Maze`](crate::maze::Maze).
+//!
+//! Every function returns a [`SolveResult`] holding the path from the start to
+//! the first exit it reaches, if any, along with statistics about the search. The
+//! `_observed` variants also report each step to a [`SearchObserver`].
+
 mod astar;
 mod bfs;
 mod dfs;
 mod dijk;
+mod graph;
+mod keys;
+mod observer;
+mod state;
+
+pub use astar::{astar, astar_observed, astar_with_heuristic, Heuristic};
+pub use bfs::{bfs, bfs_observed};
+pub use dfs::{dfs, dfs_observed};
+pub use dijk::{dijk, dijk2, dijk2_observed, dijk_observed};
+pub use graph::{Edges, Graph};
+pub use keys::{keys_astar, keys_astar_observed, keys_bfs, keys_bfs_observed};
+pub use observer::{NoopObserver, Recorder, SearchEvent, SearchObserver};
+
+use std::time::Duration;
+
+use crate::maze::node::{NodeIndex, Position};
+
+/// The outcome of a search.
+#[derive(Debug, Clone, Default)]
+pub struct SolveResult {
+    /// Positions from the start to an exit, or `None` if no exit is
+    /// reachable. Empty on graphs whose nodes have no
+    /// [`position`](Graph::position), such as networks.
+    pub path: Option<Vec<Position>>,
+    /// The exit the path leads to.
+    pub end: Option<NodeIndex>,
+    /// Nodes from the start to the exit, the same steps as `path`.
+    pub route: Vec<NodeIndex>,
+    /// Indices `i` into the path where the step to `i + 1` jumps through a
+    /// portal instead of following a corridor.
+    pub jumps: Vec<usize>,
+    /// Keys in the order the path picks them up, as indices into
+    /// [`Keys::pairs`](crate::maze::keys::Keys::pairs). Only the searches
+    /// through doors fill this in.
+    pub keys: Vec<usize>,
+    /// Sum of the link costs along the path, which is its length in pixels
+    /// on uniform terrain. On 8-connected mazes a straight step costs
+    /// [`Maze::step_costs`](crate::maze::Maze::step_costs) instead of 1. Zero
+    /// when no path was found.
+    pub cost: u64,
+    /// Nodes taken off the frontier and expanded.
+    pub expanded: usize,
+    /// Nodes pushed onto the frontier, including the start.
+    pub generated: usize,
+    /// Largest number of nodes waiting on the frontier at once.
+    pub peak_frontier: usize,
+    /// Times an already expanded node was pushed again with a shorter
+    /// distance.
+    pub reopened: usize,
+    /// Wall clock time spent searching.
+    pub elapsed: Duration,
+    /// Every node the search reached, in index order.
+    pub visited: Vec<NodeIndex>,
+}
+
+/// Manhattan distance between two positions.
+fn distance(a: Position, b: Position) -> i64 {
+    (i64::from(a.col()) - i64::from(b.col())).abs()
+        + (i64::from(a.row()) - i64::from(b.row())).abs()
+}
+
+/// Cost of the shortest walk between two positions taking diagonal steps
+/// where it can, given the cost of a straight and a diagonal step.
+fn octile(a: Position, b: Position, (straight, diagonal): (u32, u32)) -> i64 {
+    let dx = (i64::from(a.col()) - i64::from(b.col())).abs();
+    let dy = (i64::from(a.row()) - i64::from(b.row())).abs();
+    let (short, long) = (i64::min(dx, dy), i64::max(dx, dy));
+    i64::from(diagonal) * short + i64::from(straight) * (long - short)
+}
+
+/// Follows `parent` links back from `end` and stores the route in start to
+/// end order in `result`, along with its path, cost and jumps.
+fn trace_path<'a>(
+    result: &'a mut SolveResult,
+    graph: &dyn Graph,
+    end: NodeIndex,
+    parent: &[Option<NodeIndex>],
+) -> &'a [NodeIndex] {
+    let mut route = vec![end];
+    let mut child = end;
+    while let Some(c) = parent[child as usize] {
+        route.push(c);
+        child = c;
+    }
+    route.reverse();
+    store_route(result, graph, route)
+}
+
+/// Stores `route`, whose nodes must each be linked to the next, in
+/// `result`, along with the positions of its nodes as the path, its end,
+/// cost and jumps.
+fn store_route<'a>(
+    result: &'a mut SolveResult,
+    graph: &dyn Graph,
+    route: Vec<NodeIndex>,
+) -> &'a [NodeIndex] {
+    let mut cost = 0;
+    let mut jumps = Vec::new();
+    for (i, w) in route.windows(2).enumerate() {
+        if graph.jumps_to(w[0], w[1]) {
+            jumps.push(i);
+        }
+        cost += u64::from(graph.cost_to(w[0], w[1]).unwrap_or(0));
+    }
+
+    result.end = route.last().copied();
+    result.cost = cost;
+    result.jumps = jumps;
+    result.path = Some(route.iter().filter_map(|&n| graph.position(n)).collect());
+    result.route = route;
+    &result.route
+}
 
-pub use astar::astar;
-pub use bfs::bfs;
-pub use dfs::dfs;
-pub use dijk::dijk;
-pub use dijk::dijk2;
+/// The nodes flagged in `reached`.
+fn visited_nodes(reached: &[bool]) -> Vec<NodeIndex> {
+    (0..reached.len() as NodeIndex)
+        .filter(|&n| reached[n as usize])
+        .collect()
+}
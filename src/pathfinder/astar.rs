@@ -1,86 +1,172 @@
-use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap};
-use std::i64;
-use std::rc::Rc;
+use std::collections::BinaryHeap;
+use std::time::Instant;
+
+use super::state::{trace_states, State, StateMap};
+use super::{
+    distance, octile, store_route, visited_nodes, Graph, NoopObserver, SearchObserver, SolveResult,
+};
+use crate::maze::node::{NodeIndex, Position};
+
+/// Estimate of the remaining distance from a node to the nearest exit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    /// Sum of the horizontal and vertical distances. Overestimates on
+    /// 8-connected mazes.
+    Manhattan,
+    /// Distance when diagonal steps are allowed. The same as
+    /// [`Manhattan`](Heuristic::Manhattan) on 4-connected mazes.
+    Octile,
+    /// Always zero, which makes A* behave like Dijkstra's algorithm.
+    Zero,
+}
 
-use crate::maze::node::{Node, Position};
-use crate::maze::Maze;
+impl Heuristic {
+    /// Estimated cost from `from` to the closest of `goals`, given the cost
+    /// of a straight and a diagonal step as in
+    /// [`Maze::step_costs`](crate::maze::Maze::step_costs).
+    pub fn estimate(self, from: Position, goals: &[Position], steps: (u32, u32)) -> i64 {
+        let estimate = |g: Position| match self {
+            Heuristic::Manhattan => i64::from(steps.0) * distance(from, g),
+            Heuristic::Octile => octile(from, g, steps),
+            Heuristic::Zero => 0,
+        };
+        goals.iter().map(|&g| estimate(g)).min().unwrap_or(0)
+    }
+}
 
-pub fn astar(maze: &Maze) -> (Option<Vec<Position>>, usize) {
-    let start = maze.start();
-    let end = maze.end();
-    let start_pos = start.borrow().position();
-    let end_pos = end.borrow().position();
-    let start_to_end = (i64::from(start_pos.col()) - i64::from(end_pos.col())).abs()
-        + (i64::from(start_pos.row()) - i64::from(end_pos.row())).abs();
+/// A* search using the octile distance to the nearest exit as its heuristic,
+/// which is the Manhattan distance on 4-connected mazes.
+pub fn astar(graph: &dyn Graph) -> SolveResult {
+    astar_with_heuristic(graph, Heuristic::Octile)
+}
 
-    let mut queue = BinaryHeap::new();
-    let mut visited = HashMap::with_capacity(maze.count());
+/// A* search guided by `heuristic`.
+pub fn astar_with_heuristic(graph: &dyn Graph, heuristic: Heuristic) -> SolveResult {
+    astar_observed(graph, heuristic, &mut NoopObserver)
+}
+
+/// [`astar_with_heuristic`] reporting its progress to `observer`.
+pub fn astar_observed(
+    graph: &dyn Graph,
+    heuristic: Heuristic,
+    observer: &mut dyn SearchObserver,
+) -> SolveResult {
+    let visits = vec![None; graph.count()];
+    let start = graph.start_index();
+    astar_states(graph, heuristic, start, |_, n| Some(n), visits, observer).0
+}
+
+/// What A* knows about a state it has reached.
+#[derive(Debug, Clone)]
+pub(super) struct Visit<S> {
+    distance: i64,
+    parent: Option<S>,
+    closed: bool,
+}
 
-    let mut completed = false;
-    let mut count = 0;
+/// A* search from `start`, where `step(state, n)` is the state after moving
+/// on to the neighbor `n`, or `None` if the search may not. `visits` must be
+/// empty. Returns the states along the path found too.
+pub(super) fn astar_states<S: State>(
+    graph: &dyn Graph,
+    heuristic: Heuristic,
+    start: S,
+    step: impl Fn(S, NodeIndex) -> Option<S>,
+    mut visits: impl StateMap<S, Visit<S>>,
+    observer: &mut dyn SearchObserver,
+) -> (SolveResult, Vec<S>) {
+    let time = Instant::now();
+    let estimate = graph.estimator(heuristic);
+    let start_to_end = estimate(start.node());
 
-    let start_nd = NodeDist::new(Rc::clone(&start), 0, start_to_end);
-    queue.push(start_nd);
-    visited.insert(start_pos, (0, start_to_end, None));
+    let mut queue = BinaryHeap::new();
 
-    while let Some(current) = queue.pop() {
-        count += 1;
+    let mut result = SolveResult::default();
+    let mut reached = None;
+
+    queue.push(NodeDist::new(start, 0, start_to_end));
+    observer.on_push(start.node());
+    visits.insert(
+        start,
+        Visit {
+            distance: 0,
+            parent: None,
+            closed: false,
+        },
+    );
+    result.generated += 1;
+    result.peak_frontier = 1;
 
-        let cnode = current.node;
-        let cpos = cnode.borrow().position();
+    while let Some(current) = queue.pop() {
+        let cur = current.node;
         let cdist = current.distance;
 
-        if cpos == end_pos {
-            completed = true;
+        match visits.get_mut(cur) {
+            // a shorter route to this state was already expanded
+            Some(visit) if cdist > visit.distance => continue,
+            Some(visit) => visit.closed = true,
+            None => {}
+        }
+        result.expanded += 1;
+        observer.on_pop(cur.node());
+
+        if graph.is_end(cur.node()) {
+            reached = Some(cur);
             break;
         }
 
-        for maybe_n in cnode.borrow().neighbors() {
-            if let Some(n) = maybe_n {
-                let npos = n.borrow().position();
-                let mut ndist = (i64::from(npos.col()) - i64::from(cpos.col())).abs()
-                    + (i64::from(npos.row()) - i64::from(cpos.row())).abs();
-                ndist += cdist;
-                let ndist_to_end = (i64::from(npos.col()) - i64::from(end_pos.col())).abs()
-                    + (i64::from(npos.row()) - i64::from(end_pos.row())).abs();
-
-                if !visited.contains_key(&npos)
-                    || ndist + ndist_to_end < visited[&npos].0 + visited[&npos].1
-                {
-                    let n_nd = NodeDist::new(Rc::clone(&n), ndist, ndist_to_end);
-                    queue.push(n_nd);
-                    visited.insert(npos, (ndist, ndist_to_end, Some(cpos)));
+        for (n, cost) in graph.edges(cur.node()) {
+            let next = match step(cur, n) {
+                Some(next) => next,
+                None => continue,
+            };
+            let ndist = cdist + i64::from(cost);
+
+            let visit = visits.get(next);
+            if visit.is_none_or(|v| ndist < v.distance) {
+                if visit.is_some_and(|v| v.closed) {
+                    result.reopened += 1;
                 }
+                let ndist_to_end = estimate(n);
+                queue.push(NodeDist::new(next, ndist, ndist_to_end));
+                observer.on_relax(n, cur.node());
+                observer.on_push(n);
+                visits.insert(
+                    next,
+                    Visit {
+                        distance: ndist,
+                        parent: Some(cur),
+                        closed: false,
+                    },
+                );
+                result.generated += 1;
             }
         }
+        result.peak_frontier = usize::max(result.peak_frontier, queue.len());
     }
 
-    if completed {
-        let mut path = Vec::new();
-        path.push(end_pos);
-        let mut current = visited[&end_pos];
-        while let (_, _, Some(cpos)) = current {
-            path.push(cpos);
-            current = visited[&cpos];
-        }
-
-        (Some(path.into_iter().rev().collect()), count)
-    } else {
-        (None, count)
+    let mut states = Vec::new();
+    if let Some(end) = reached {
+        states = trace_states(&visits, end, |v| v.parent);
+        let route: Vec<NodeIndex> = states.iter().map(|s| s.node()).collect();
+        let route = store_route(&mut result, graph, route);
+        observer.on_path_found(route);
     }
+    result.visited = visited_nodes(&visits.reached(graph.count()));
+    result.elapsed = time.elapsed();
+    (result, states)
 }
 
 #[derive(Debug, Eq, Clone)]
-struct NodeDist {
-    node: Rc<RefCell<Node>>,
+struct NodeDist<S> {
+    node: S,
     distance: i64,
     dist_to_end: i64,
 }
 
-impl NodeDist {
-    pub fn new(node: Rc<RefCell<Node>>, distance: i64, dist_to_end: i64) -> NodeDist {
+impl<S> NodeDist<S> {
+    pub fn new(node: S, distance: i64, dist_to_end: i64) -> NodeDist<S> {
         NodeDist {
             node,
             distance,
@@ -89,19 +175,19 @@ impl NodeDist {
     }
 }
 
-impl PartialEq for NodeDist {
+impl<S> PartialEq for NodeDist<S> {
     fn eq(&self, other: &Self) -> bool {
         self.distance + self.dist_to_end == other.distance + other.dist_to_end
     }
 }
 
-impl PartialOrd for NodeDist {
+impl<S: Eq> PartialOrd for NodeDist<S> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some((other.distance + other.dist_to_end).cmp(&(self.distance + self.dist_to_end)))
+        Some(self.cmp(other))
     }
 }
 
-impl Ord for NodeDist {
+impl<S: Eq> Ord for NodeDist<S> {
     fn cmp(&self, other: &Self) -> Ordering {
         (other.distance + other.dist_to_end).cmp(&(self.distance + self.dist_to_end))
     }
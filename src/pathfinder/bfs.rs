@@ -1,58 +1,77 @@
-use std::collections::HashMap;
 use std::collections::VecDeque;
-use std::rc::Rc;
+use std::time::Instant;
 
-use crate::maze::node::Position;
-use crate::maze::Maze;
+use super::state::{trace_states, State, StateMap};
+use super::{store_route, visited_nodes, Graph, NoopObserver, SearchObserver, SolveResult};
+use crate::maze::node::NodeIndex;
 
-pub fn bfs(maze: &Maze) -> (Option<Vec<Position>>, usize) {
-    let start = maze.start();
-    let end = maze.end();
+/// Breadth-first search. Finds the path with the fewest nodes.
+pub fn bfs(graph: &dyn Graph) -> SolveResult {
+    bfs_observed(graph, &mut NoopObserver)
+}
+
+/// [`bfs`] reporting its progress to `observer`.
+pub fn bfs_observed(graph: &dyn Graph, observer: &mut dyn SearchObserver) -> SolveResult {
+    let parent = vec![None; graph.count()];
+    bfs_states(graph, graph.start_index(), |_, n| Some(n), parent, observer).0
+}
 
-    let mut completed = false;
+/// Breadth-first search from `start`, where `step(state, n)` is the state
+/// after moving on to the neighbor `n`, or `None` if the search may not.
+/// `parent` must be empty. Returns the states along the path found too.
+pub(super) fn bfs_states<S: State>(
+    graph: &dyn Graph,
+    start: S,
+    step: impl Fn(S, NodeIndex) -> Option<S>,
+    mut parent: impl StateMap<S, Option<S>>,
+    observer: &mut dyn SearchObserver,
+) -> (SolveResult, Vec<S>) {
+    let time = Instant::now();
+
+    let mut result = SolveResult::default();
+    let mut reached = None;
     let mut queue = VecDeque::new();
-    let mut visited = HashMap::with_capacity(maze.count());
-    let mut count = 0;
 
-    visited.insert(start.borrow().position(), None);
+    parent.insert(start, None);
     queue.push_back(start);
+    observer.on_push(start.node());
+    result.generated += 1;
+    result.peak_frontier = 1;
 
     while let Some(current) = queue.pop_front() {
-        count += 1;
+        let cur = current.node();
+        result.expanded += 1;
+        observer.on_pop(cur);
 
-        if current == end {
-            completed = true;
+        if graph.is_end(cur) {
+            reached = Some(current);
             break;
         }
 
-        let cpos = current.borrow().position();
-
-        for maybe_n in current.borrow().neighbors() {
-            if let Some(n) = maybe_n {
-                let npos = n.borrow().position();
-
-                if !visited.contains_key(&npos) {
-                    queue.push_back(Rc::clone(&n));
-                    visited.insert(npos, Some(cpos));
-                }
+        for (n, _) in graph.edges(cur) {
+            let next = match step(current, n) {
+                Some(next) => next,
+                None => continue,
+            };
+            if parent.get(next).is_none() {
+                parent.insert(next, Some(current));
+                queue.push_back(next);
+                observer.on_relax(n, cur);
+                observer.on_push(n);
+                result.generated += 1;
             }
         }
+        result.peak_frontier = usize::max(result.peak_frontier, queue.len());
     }
 
-    if completed {
-        let epos = end.borrow().position();
-
-        let mut path = Vec::new();
-        let mut current = visited[&epos];
-
-        path.push(epos);
-        while let Some(cpos) = current {
-            path.push(cpos);
-            current = visited[&cpos];
-        }
-
-        (Some(path.into_iter().rev().collect()), count)
-    } else {
-        (None, count)
+    let mut states = Vec::new();
+    if let Some(end) = reached {
+        states = trace_states(&parent, end, |&p| p);
+        let route: Vec<NodeIndex> = states.iter().map(|s| s.node()).collect();
+        let route = store_route(&mut result, graph, route);
+        observer.on_path_found(route);
     }
+    result.visited = visited_nodes(&parent.reached(graph.count()));
+    result.elapsed = time.elapsed();
+    (result, states)
 }
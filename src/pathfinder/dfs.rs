@@ -1,57 +1,56 @@
-use std::collections::HashMap;
-use std::rc::Rc;
+use std::time::Instant;
 
-use crate::maze::node::Position;
-use crate::maze::Maze;
+use super::{trace_path, visited_nodes, Graph, NoopObserver, SearchObserver, SolveResult};
 
-pub fn dfs(maze: &Maze) -> (Option<Vec<Position>>, usize) {
-    let start = maze.start();
-    let end = maze.end();
+/// Depth-first search. Fast on perfect mazes but the path is not shortest.
+pub fn dfs(graph: &dyn Graph) -> SolveResult {
+    dfs_observed(graph, &mut NoopObserver)
+}
+
+/// [`dfs`] reporting its progress to `observer`.
+pub fn dfs_observed(graph: &dyn Graph, observer: &mut dyn SearchObserver) -> SolveResult {
+    let time = Instant::now();
+    let start = graph.start_index();
 
+    let mut result = SolveResult::default();
+    let mut reached = None;
     let mut stack = Vec::new();
-    let mut visited = HashMap::with_capacity(maze.count());
-    let mut completed = false;
-    let mut count = 0;
+    let mut visited = vec![false; graph.count()];
+    let mut parent = vec![None; graph.count()];
 
-    stack.push(Rc::clone(&start));
-    visited.insert(start.borrow().position(), None);
+    visited[start as usize] = true;
+    stack.push(start);
+    observer.on_push(start);
+    result.generated += 1;
+    result.peak_frontier = 1;
 
     while let Some(current) = stack.pop() {
-        count += 1;
+        result.expanded += 1;
+        observer.on_pop(current);
 
-        if current == end {
-            completed = true;
+        if graph.is_end(current) {
+            reached = Some(current);
             break;
         }
 
-        let cpos = current.borrow().position();
-
-        for maybe_n in current.borrow().neighbors() {
-            if let Some(n) = maybe_n {
-                let npos = n.borrow().position();
-
-                if !visited.contains_key(&npos) {
-                    stack.push(Rc::clone(&n));
-                    visited.insert(npos, Some(cpos));
-                }
+        for (n, _) in graph.edges(current) {
+            if !visited[n as usize] {
+                visited[n as usize] = true;
+                parent[n as usize] = Some(current);
+                stack.push(n);
+                observer.on_relax(n, current);
+                observer.on_push(n);
+                result.generated += 1;
             }
         }
+        result.peak_frontier = usize::max(result.peak_frontier, stack.len());
     }
 
-    if completed {
-        let epos = end.borrow().position();
-
-        let mut path = Vec::new();
-        let mut current = visited[&epos];
-
-        path.push(epos);
-        while let Some(cpos) = current {
-            path.push(cpos);
-            current = visited[&cpos];
-        }
-
-        (Some(path.into_iter().rev().collect()), count)
-    } else {
-        (None, count)
+    if let Some(end) = reached {
+        let route = trace_path(&mut result, graph, end, &parent);
+        observer.on_path_found(route);
     }
+    result.visited = visited_nodes(&visited);
+    result.elapsed = time.elapsed();
+    result
 }
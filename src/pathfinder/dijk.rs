@@ -1,144 +1,168 @@
 use priority_queue::PriorityQueue;
 
-use std::cell::RefCell;
 use std::cmp::Ordering;
-use std::collections::{BinaryHeap, HashMap};
-use std::i64;
-use std::rc::Rc;
+use std::collections::BinaryHeap;
+use std::time::Instant;
 
-use crate::maze::node::{Node, Position};
-use crate::maze::Maze;
+use super::{trace_path, visited_nodes, Graph, NoopObserver, SearchObserver, SolveResult};
+use crate::maze::node::NodeIndex;
 
-pub fn dijk(maze: &Maze) -> (Option<Vec<Position>>, usize) {
-    let start = maze.start();
-    let end = maze.end();
-    let start_pos = start.borrow().position();
-    let end_pos = end.borrow().position();
+/// Dijkstra's algorithm on a binary heap, weighting links by their cost.
+pub fn dijk(graph: &dyn Graph) -> SolveResult {
+    dijk_observed(graph, &mut NoopObserver)
+}
+
+/// [`dijk`] reporting its progress to `observer`.
+pub fn dijk_observed(graph: &dyn Graph, observer: &mut dyn SearchObserver) -> SolveResult {
+    let time = Instant::now();
+    let start = graph.start_index();
 
     let mut queue = BinaryHeap::new();
-    let mut visited = HashMap::with_capacity(maze.count());
+    let mut dist: Vec<Option<i64>> = vec![None; graph.count()];
+    let mut parent = vec![None; graph.count()];
+    let mut closed = vec![false; graph.count()];
 
-    let mut completed = false;
-    let mut count = 0;
+    let mut result = SolveResult::default();
+    let mut reached = None;
 
-    let start_nd = NodeDist::new(Rc::clone(&start), 0);
-    queue.push(start_nd);
-    visited.insert(start_pos, (0, None));
+    queue.push(NodeDist::new(start, 0));
+    observer.on_push(start);
+    dist[start as usize] = Some(0);
+    result.generated += 1;
+    result.peak_frontier = 1;
 
     while let Some(current) = queue.pop() {
-        count += 1;
-
-        let cnode = current.node;
-        let cpos = cnode.borrow().position();
+        let cur = current.node;
         let cdist = current.distance;
 
-        if cpos == end_pos {
-            completed = true;
+        // a shorter route to this node was already expanded
+        if Some(cdist) > dist[cur as usize] {
+            continue;
+        }
+        result.expanded += 1;
+        observer.on_pop(cur);
+        closed[cur as usize] = true;
+
+        if graph.is_end(cur) {
+            reached = Some(cur);
             break;
         }
 
-        for maybe_n in cnode.borrow().neighbors() {
-            if let Some(n) = maybe_n {
-                let npos = n.borrow().position();
-                let mut ndist = (i64::from(npos.col()) - i64::from(cpos.col())).abs()
-                    + (i64::from(npos.row()) - i64::from(cpos.row())).abs();
-                ndist += cdist;
-
-                if !visited.contains_key(&npos) || ndist < visited[&npos].0 {
-                    let n_nd = NodeDist::new(Rc::clone(&n), ndist);
-                    queue.push(n_nd);
-                    visited.insert(npos, (ndist, Some(cpos)));
+        for (n, cost) in graph.edges(cur) {
+            let ndist = cdist + i64::from(cost);
+
+            if dist[n as usize].is_none_or(|d| ndist < d) {
+                if closed[n as usize] {
+                    closed[n as usize] = false;
+                    result.reopened += 1;
                 }
+                queue.push(NodeDist::new(n, ndist));
+                observer.on_relax(n, cur);
+                observer.on_push(n);
+                dist[n as usize] = Some(ndist);
+                parent[n as usize] = Some(cur);
+                result.generated += 1;
             }
         }
+        result.peak_frontier = usize::max(result.peak_frontier, queue.len());
     }
 
-    if completed {
-        let mut path = Vec::new();
-        path.push(end_pos);
-        let mut current = visited[&end_pos];
-        while let (_, Some(cpos)) = current {
-            path.push(cpos);
-            current = visited[&cpos];
-        }
-
-        (Some(path.into_iter().rev().collect()), count)
-    } else {
-        (None, count)
+    if let Some(end) = reached {
+        let route = trace_path(&mut result, graph, end, &parent);
+        observer.on_path_found(route);
     }
+    let reached: Vec<bool> = dist.iter().map(Option::is_some).collect();
+    result.visited = visited_nodes(&reached);
+    result.elapsed = time.elapsed();
+    result
 }
 
-pub fn dijk2(maze: &Maze) -> (Option<Vec<Position>>, usize) {
-    let start = maze.start();
-    let end = maze.end();
-    let start_pos = start.borrow().position();
-    let end_pos = end.borrow().position();
+/// Dijkstra's algorithm on an indexed priority queue that updates entries
+/// in place instead of pushing duplicates.
+pub fn dijk2(graph: &dyn Graph) -> SolveResult {
+    dijk2_observed(graph, &mut NoopObserver)
+}
 
-    let mut queue = PriorityQueue::new();
-    let mut visited = HashMap::with_capacity(maze.count());
+/// [`dijk2`] reporting its progress to `observer`.
+pub fn dijk2_observed(graph: &dyn Graph, observer: &mut dyn SearchObserver) -> SolveResult {
+    let time = Instant::now();
+    let start = graph.start_index();
 
-    let mut completed = false;
-    let mut count = 0;
+    let mut queue = PriorityQueue::new();
+    let mut dist: Vec<Option<i64>> = vec![None; graph.count()];
+    let mut parent = vec![None; graph.count()];
+    let mut closed = vec![false; graph.count()];
 
-    let start_nd = NodeDist::new(Rc::clone(&start), 0);
-    queue.push(start_pos, start_nd);
-    visited.insert(start_pos, (0, None));
+    let mut result = SolveResult::default();
+    let mut reached = None;
 
-    while let Some((cpos, current)) = queue.pop() {
-        count += 1;
+    queue.push(start, NodeDist::new(start, 0));
+    observer.on_push(start);
+    dist[start as usize] = Some(0);
+    result.generated += 1;
+    result.peak_frontier = 1;
 
-        let cnode = current.node;
+    while let Some((cur, current)) = queue.pop() {
         let cdist = current.distance;
+        result.expanded += 1;
+        observer.on_pop(cur);
+        closed[cur as usize] = true;
 
-        if cpos == end_pos {
-            completed = true;
+        if graph.is_end(cur) {
+            reached = Some(cur);
             break;
         }
 
-        for maybe_n in cnode.borrow().neighbors() {
-            if let Some(n) = maybe_n {
-                let npos = n.borrow().position();
-                let mut ndist = (i64::from(npos.col()) - i64::from(cpos.col())).abs()
-                    + (i64::from(npos.row()) - i64::from(cpos.row())).abs();
-                ndist += cdist;
-
-                if visited.contains_key(&npos) && ndist < visited[&npos].0 {
-                    let n_nd = NodeDist::new(Rc::clone(&n), ndist);
-                    queue.change_priority(&npos, n_nd);
-                    visited.insert(npos, (ndist, Some(cpos)));
+        for (n, cost) in graph.edges(cur) {
+            let ndist = cdist + i64::from(cost);
+
+            match dist[n as usize] {
+                Some(d) if ndist >= d => {}
+                Some(_) => {
+                    if closed[n as usize] {
+                        closed[n as usize] = false;
+                        result.reopened += 1;
+                        result.generated += 1;
+                        queue.push(n, NodeDist::new(n, ndist));
+                        observer.on_push(n);
+                    } else {
+                        queue.change_priority(&n, NodeDist::new(n, ndist));
+                    }
+                    observer.on_relax(n, cur);
+                    dist[n as usize] = Some(ndist);
+                    parent[n as usize] = Some(cur);
                 }
-                if !visited.contains_key(&npos) {
-                    let n_nd = NodeDist::new(Rc::clone(&n), ndist);
-                    queue.push(npos, n_nd);
-                    visited.insert(npos, (ndist, Some(cpos)));
+                None => {
+                    queue.push(n, NodeDist::new(n, ndist));
+                    observer.on_relax(n, cur);
+                    observer.on_push(n);
+                    dist[n as usize] = Some(ndist);
+                    parent[n as usize] = Some(cur);
+                    result.generated += 1;
                 }
             }
         }
+        result.peak_frontier = usize::max(result.peak_frontier, queue.len());
     }
 
-    if completed {
-        let mut path = Vec::new();
-        path.push(end_pos);
-        let mut current = visited[&end_pos];
-        while let (_, Some(cpos)) = current {
-            path.push(cpos);
-            current = visited[&cpos];
-        }
-
-        (Some(path.into_iter().rev().collect()), count)
-    } else {
-        (None, count)
+    if let Some(end) = reached {
+        let route = trace_path(&mut result, graph, end, &parent);
+        observer.on_path_found(route);
     }
+    let reached: Vec<bool> = dist.iter().map(Option::is_some).collect();
+    result.visited = visited_nodes(&reached);
+    result.elapsed = time.elapsed();
+    result
 }
 
 #[derive(Debug, Eq, Clone)]
 struct NodeDist {
-    node: Rc<RefCell<Node>>,
+    node: NodeIndex,
     distance: i64,
 }
 
 impl NodeDist {
-    pub fn new(node: Rc<RefCell<Node>>, distance: i64) -> NodeDist {
+    pub fn new(node: NodeIndex, distance: i64) -> NodeDist {
         NodeDist { node, distance }
     }
 }
@@ -151,7 +175,7 @@ impl PartialEq for NodeDist {
 
 impl PartialOrd for NodeDist {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(other.distance.cmp(&self.distance))
+        Some(self.cmp(other))
     }
 }
 
@@ -0,0 +1,176 @@
+use std::iter::Zip;
+use std::slice;
+
+use crate::maze::node::{NodeIndex, Position};
+use crate::maze::Maze;
+
+use super::Heuristic;
+
+/// A graph the searches run on: nodes in an arena, a start and one or more
+/// exits. [`Maze`] is built from an image and
+/// [`Network`](crate::network::Network) is loaded from a graph file.
+///
+/// Graphs must be `Sync` so several searches can share one, see
+/// [`solve_parallel`](crate::algos::solve_parallel).
+pub trait Graph: Sync {
+    /// Number of nodes in the graph.
+    fn count(&self) -> usize;
+
+    /// Every edge leaving the node at `index`, with the cost of taking it.
+    fn edges(&self, index: NodeIndex) -> Edges<'_>;
+
+    /// The node the search starts from.
+    fn start_index(&self) -> NodeIndex;
+
+    /// Every exit. There is always at least one.
+    fn ends(&self) -> &[NodeIndex];
+
+    /// Whether the node at `index` is one of the exits.
+    fn is_end(&self, index: NodeIndex) -> bool {
+        self.ends().contains(&index)
+    }
+
+    /// Cost of the cheapest edge from `from` to `to`, if there is one.
+    fn cost_to(&self, from: NodeIndex, to: NodeIndex) -> Option<u32> {
+        self.edges(from)
+            .filter(|&(n, _)| n == to)
+            .map(|(_, c)| c)
+            .min()
+    }
+
+    /// Whether the cheapest step from `from` to its neighbor `to` jumps, see
+    /// [`SolveResult::jumps`](super::SolveResult::jumps).
+    fn jumps_to(&self, from: NodeIndex, to: NodeIndex) -> bool {
+        let _ = (from, to);
+        false
+    }
+
+    /// The pixel the node at `index` lies on, for graphs drawn as images.
+    fn position(&self, index: NodeIndex) -> Option<Position> {
+        let _ = index;
+        None
+    }
+
+    /// A lower bound of `heuristic` on the cost from a node to the nearest
+    /// exit, for A*.
+    fn estimator(&self, heuristic: Heuristic) -> Box<dyn Fn(NodeIndex) -> i64 + '_>;
+
+    /// The graph as a [`Maze`], for the searches through its doors.
+    fn as_maze(&self) -> Option<&Maze> {
+        None
+    }
+}
+
+/// The edges leaving one node, each with the cost of taking it, see
+/// [`Graph::edges`].
+#[derive(Debug, Clone)]
+pub struct Edges<'a> {
+    corridors: Zip<slice::Iter<'a, Option<NodeIndex>>, slice::Iter<'a, u32>>,
+    links: slice::Iter<'a, (NodeIndex, u32)>,
+}
+
+// associate functions
+impl<'a> Edges<'a> {
+    /// The neighbors set in `neighbors`, with the cost at the same index of
+    /// `costs`, followed by `links`.
+    pub(crate) fn new(
+        neighbors: &'a [Option<NodeIndex>],
+        costs: &'a [u32],
+        links: &'a [(NodeIndex, u32)],
+    ) -> Edges<'a> {
+        Edges {
+            corridors: neighbors.iter().zip(costs),
+            links: links.iter(),
+        }
+    }
+
+    /// Only `links`.
+    pub(crate) fn from_links(links: &'a [(NodeIndex, u32)]) -> Edges<'a> {
+        Edges::new(&[], &[], links)
+    }
+}
+
+impl Iterator for Edges<'_> {
+    type Item = (NodeIndex, u32);
+
+    fn next(&mut self) -> Option<(NodeIndex, u32)> {
+        self.corridors
+            .find_map(|(n, &c)| n.map(|n| (n, c)))
+            .or_else(|| self.links.next().copied())
+    }
+}
+
+impl Graph for Maze {
+    fn count(&self) -> usize {
+        Maze::count(self)
+    }
+
+    fn edges(&self, index: NodeIndex) -> Edges<'_> {
+        Maze::node(self, index).edges()
+    }
+
+    fn start_index(&self) -> NodeIndex {
+        Maze::start_index(self)
+    }
+
+    fn ends(&self) -> &[NodeIndex] {
+        Maze::ends(self)
+    }
+
+    fn is_end(&self, index: NodeIndex) -> bool {
+        Maze::is_end(self, index)
+    }
+
+    fn jumps_to(&self, from: NodeIndex, to: NodeIndex) -> bool {
+        Maze::node(self, from).jumps_to(to)
+    }
+
+    fn position(&self, index: NodeIndex) -> Option<Position> {
+        Some(Maze::node(self, index).position())
+    }
+
+    /// The estimate of `heuristic` from the position of a node to the
+    /// nearest exit, taking the links of the maze into account.
+    fn estimator(&self, heuristic: Heuristic) -> Box<dyn Fn(NodeIndex) -> i64 + '_> {
+        let steps = self.step_costs();
+        let end_pos: Vec<Position> = Maze::ends(self)
+            .iter()
+            .map(|&e| Maze::node(self, e).position())
+            .collect();
+
+        // a portal or other link may lead closer to an exit than walking, so
+        // the estimate through the nearest link bounds the one straight to
+        // the exit
+        let link_pos: Vec<Position> = self
+            .links()
+            .iter()
+            .flatten()
+            .map(|&p| Maze::node(self, p).position())
+            .collect();
+        let link_cost = self
+            .links()
+            .iter()
+            .filter_map(|&[a, b]| Maze::node(self, a).cost_to(b))
+            .min()
+            .unwrap_or(0);
+        let after_link = link_pos
+            .iter()
+            .map(|&p| heuristic.estimate(p, &end_pos, steps))
+            .min();
+        Box::new(move |index: NodeIndex| {
+            let pos = Maze::node(self, index).position();
+            let direct = heuristic.estimate(pos, &end_pos, steps);
+            match after_link {
+                Some(after) => i64::min(
+                    direct,
+                    heuristic.estimate(pos, &link_pos, steps) + i64::from(link_cost) + after,
+                ),
+                None => direct,
+            }
+        })
+    }
+
+    fn as_maze(&self) -> Option<&Maze> {
+        Some(self)
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use super::astar::astar_states;
+use super::bfs::bfs_states;
+use super::state::State;
+use super::{Heuristic, NoopObserver, SearchObserver, SolveResult};
+use crate::maze::keys::KeySet;
+use crate::maze::node::NodeIndex;
+use crate::maze::Maze;
+
+/// A node together with the keys held on arriving there.
+type KeyState = (NodeIndex, KeySet);
+
+impl State for KeyState {
+    fn node(self) -> NodeIndex {
+        self.0
+    }
+}
+
+/// Breadth-first search through doors. Each node is searched once for every
+/// set of keys it can be reached with, so the path may walk back the way it
+/// came after picking up a key. Finds the path with the fewest nodes.
+pub fn keys_bfs(maze: &Maze) -> SolveResult {
+    keys_bfs_observed(maze, &mut NoopObserver)
+}
+
+/// [`keys_bfs`] reporting its progress to `observer`.
+pub fn keys_bfs_observed(maze: &Maze, observer: &mut dyn SearchObserver) -> SolveResult {
+    let step = |(_, keys), n| step(maze, keys, n);
+    let (mut result, states) = bfs_states(maze, start_state(maze), step, HashMap::new(), observer);
+    result.keys = pickups(&states);
+    result
+}
+
+/// A* search through doors, over the same states as [`keys_bfs`]. The
+/// heuristic ignores doors, so the path found is the cheapest.
+pub fn keys_astar(maze: &Maze, heuristic: Heuristic) -> SolveResult {
+    keys_astar_observed(maze, heuristic, &mut NoopObserver)
+}
+
+/// [`keys_astar`] reporting its progress to `observer`.
+pub fn keys_astar_observed(
+    maze: &Maze,
+    heuristic: Heuristic,
+    observer: &mut dyn SearchObserver,
+) -> SolveResult {
+    let step = |(_, keys), n| step(maze, keys, n);
+    let (mut result, states) = astar_states(
+        maze,
+        heuristic,
+        start_state(maze),
+        step,
+        HashMap::new(),
+        observer,
+    );
+    result.keys = pickups(&states);
+    result
+}
+
+/// The start node, holding the key that lies on it if any.
+fn start_state(maze: &Maze) -> KeyState {
+    let start = maze.start_index();
+    (start, pick_up(maze, 0, start))
+}
+
+/// The state after stepping onto `node` holding `keys`, or `None` if `node`
+/// is a door none of them opens.
+fn step(maze: &Maze, keys: KeySet, node: NodeIndex) -> Option<KeyState> {
+    match maze.door_at(node) {
+        Some(door) if keys & (1 << door) == 0 => None,
+        _ => Some((node, pick_up(maze, keys, node))),
+    }
+}
+
+/// `keys` with the key lying on `node` added.
+fn pick_up(maze: &Maze, keys: KeySet, node: NodeIndex) -> KeySet {
+    match maze.key_at(node) {
+        Some(key) => keys | (1 << key),
+        None => keys,
+    }
+}
+
+/// The keys picked up along `states`, in order.
+fn pickups(states: &[KeyState]) -> Vec<usize> {
+    states
+        .iter()
+        .scan(0, |held: &mut KeySet, &(_, keys)| {
+            let new = keys & !*held;
+            *held = keys;
+            Some(new)
+        })
+        .filter(|&new| new != 0)
+        .map(|new| new.trailing_zeros() as usize)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::keys::Keys;
+    use crate::maze::node::Position;
+    use crate::maze::{Endpoint, Maze, MazeOptions};
+    use image::{Rgb, RgbImage};
+
+    const PAIRS: [(Rgb<u8>, Rgb<u8>); 2] = [
+        (Rgb { data: [255, 0, 0] }, Rgb { data: [128, 0, 0] }),
+        (Rgb { data: [0, 255, 0] }, Rgb { data: [0, 128, 0] }),
+    ];
+
+    /// A maze drawn as text, where `a` and `b` are keys opening the doors
+    /// `A` and `B`, and `S` and `E` are the start and the exit.
+    fn maze(rows: &[&str]) -> Maze {
+        let mut img = RgbImage::from_pixel(
+            rows[0].len() as u32,
+            rows.len() as u32,
+            Rgb {
+                data: [255, 255, 255],
+            },
+        );
+        let mut options = MazeOptions {
+            keys: Keys {
+                pairs: PAIRS.to_vec(),
+                tolerance: 0,
+            },
+            ..MazeOptions::default()
+        };
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let pos = Position::new(x as u32, y as u32);
+                match c {
+                    '#' => img[(x as u32, y as u32)] = Rgb { data: [0, 0, 0] },
+                    'a' => img[(x as u32, y as u32)] = PAIRS[0].0,
+                    'A' => img[(x as u32, y as u32)] = PAIRS[0].1,
+                    'b' => img[(x as u32, y as u32)] = PAIRS[1].0,
+                    'B' => img[(x as u32, y as u32)] = PAIRS[1].1,
+                    'S' => options.start = Endpoint::At(pos),
+                    'E' => options.ends = vec![Endpoint::At(pos)],
+                    _ => {}
+                }
+            }
+        }
+        Maze::with_options(&img, &options).unwrap()
+    }
+
+    #[test]
+    fn fetches_the_key_before_the_door() {
+        let maze = maze(&["#########", "#a.S.A.E#", "#########"]);
+        for result in &[keys_bfs(&maze), keys_astar(&maze, Heuristic::Octile)] {
+            assert_eq!(result.keys, vec![0]);
+            assert_eq!(result.cost, 8);
+            let path = result.path.as_ref().unwrap();
+            let key = path.iter().position(|&p| p == Position::new(1, 1));
+            let door = path.iter().position(|&p| p == Position::new(5, 1));
+            assert!(key.unwrap() < door.unwrap());
+        }
+    }
+
+    #[test]
+    fn keys_in_the_order_they_are_picked_up() {
+        // b lies behind A, and B stands between the start and the exit
+        let maze = maze(&[
+            "#################",
+            "#b..A..a.S..B..E#",
+            "#################",
+        ]);
+        for result in &[keys_bfs(&maze), keys_astar(&maze, Heuristic::Octile)] {
+            assert_eq!(result.keys, vec![0, 1]);
+            assert_eq!(result.cost, 22);
+        }
+    }
+
+    #[test]
+    fn locked_door_blocks_the_way() {
+        let maze = maze(&["#######", "#S.A.E#", "#######"]);
+        assert!(keys_bfs(&maze).path.is_none());
+        assert!(keys_astar(&maze, Heuristic::Octile).path.is_none());
+    }
+
+    #[test]
+    fn plain_search_walks_through_doors() {
+        let maze = maze(&["#######", "#S.A.E#", "#######"]);
+        assert_eq!(crate::pathfinder::bfs(&maze).cost, 4);
+    }
+}
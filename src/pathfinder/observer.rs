@@ -0,0 +1,77 @@
+use crate::maze::node::NodeIndex;
+
+/// Receives events from a running search.
+///
+/// Every method does nothing by default, so an observer only implements the
+/// events it cares about. Nodes are reported by index, as in
+/// [`Graph::edges`](super::Graph::edges).
+pub trait SearchObserver {
+    /// `node` was added to the frontier.
+    fn on_push(&mut self, node: NodeIndex) {
+        let _ = node;
+    }
+
+    /// `node` was taken off the frontier and is about to be expanded.
+    fn on_pop(&mut self, node: NodeIndex) {
+        let _ = node;
+    }
+
+    /// The best known route to `node` now arrives from `parent`.
+    fn on_relax(&mut self, node: NodeIndex, parent: NodeIndex) {
+        let _ = (node, parent);
+    }
+
+    /// The search reached the exit along the nodes of `route`.
+    fn on_path_found(&mut self, route: &[NodeIndex]) {
+        let _ = route;
+    }
+}
+
+/// An observer that ignores every event.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopObserver;
+
+impl SearchObserver for NoopObserver {}
+
+/// One event of a search, as kept by a [`Recorder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchEvent {
+    Push(NodeIndex),
+    Pop(NodeIndex),
+    Relax { node: NodeIndex, parent: NodeIndex },
+}
+
+/// An observer that keeps every push, pop and relaxation in order, e.g. to
+/// animate the search with [`render::animate`](crate::render::animate).
+#[derive(Debug, Clone, Default)]
+pub struct Recorder {
+    events: Vec<SearchEvent>,
+}
+
+// associate functions
+impl Recorder {
+    pub fn new() -> Recorder {
+        Recorder::default()
+    }
+}
+
+// methods
+impl Recorder {
+    pub fn events(&self) -> &[SearchEvent] {
+        &self.events
+    }
+}
+
+impl SearchObserver for Recorder {
+    fn on_push(&mut self, node: NodeIndex) {
+        self.events.push(SearchEvent::Push(node));
+    }
+
+    fn on_pop(&mut self, node: NodeIndex) {
+        self.events.push(SearchEvent::Pop(node));
+    }
+
+    fn on_relax(&mut self, node: NodeIndex, parent: NodeIndex) {
+        self.events.push(SearchEvent::Relax { node, parent });
+    }
+}
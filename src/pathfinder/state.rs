@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::maze::node::NodeIndex;
+
+/// Where a search stands: a node, or a node together with whatever else the
+/// search has to tell apart, such as the keys held.
+pub(super) trait State: Copy + Eq + Hash {
+    fn node(self) -> NodeIndex;
+}
+
+impl State for NodeIndex {
+    fn node(self) -> NodeIndex {
+        self
+    }
+}
+
+/// Values kept for every state a search has reached.
+pub(super) trait StateMap<S, V> {
+    fn get(&self, state: S) -> Option<&V>;
+    fn get_mut(&mut self, state: S) -> Option<&mut V>;
+    fn insert(&mut self, state: S, value: V);
+    /// Flags the nodes of the `count` in the graph that some state was
+    /// reached on.
+    fn reached(&self, count: usize) -> Vec<bool>;
+}
+
+/// One slot per node, for searches whose states are the nodes themselves.
+impl<V> StateMap<NodeIndex, V> for Vec<Option<V>> {
+    fn get(&self, node: NodeIndex) -> Option<&V> {
+        self[node as usize].as_ref()
+    }
+
+    fn get_mut(&mut self, node: NodeIndex) -> Option<&mut V> {
+        self[node as usize].as_mut()
+    }
+
+    fn insert(&mut self, node: NodeIndex, value: V) {
+        self[node as usize] = Some(value);
+    }
+
+    fn reached(&self, _count: usize) -> Vec<bool> {
+        self.iter().map(Option::is_some).collect()
+    }
+}
+
+impl<S: State, V> StateMap<S, V> for HashMap<S, V> {
+    fn get(&self, state: S) -> Option<&V> {
+        HashMap::get(self, &state)
+    }
+
+    fn get_mut(&mut self, state: S) -> Option<&mut V> {
+        HashMap::get_mut(self, &state)
+    }
+
+    fn insert(&mut self, state: S, value: V) {
+        HashMap::insert(self, state, value);
+    }
+
+    fn reached(&self, count: usize) -> Vec<bool> {
+        let mut reached = vec![false; count];
+        for state in self.keys() {
+            reached[state.node() as usize] = true;
+        }
+        reached
+    }
+}
+
+/// Follows the links `parent` finds in `map` back from `end`, and returns
+/// the states in start to end order.
+pub(super) fn trace_states<S: State, V>(
+    map: &impl StateMap<S, V>,
+    end: S,
+    parent: impl Fn(&V) -> Option<S>,
+) -> Vec<S> {
+    let mut states = vec![end];
+    let mut child = end;
+    while let Some(c) = map.get(child).and_then(&parent) {
+        states.push(c);
+        child = c;
+    }
+    states.reverse();
+    states
+}
@@ -0,0 +1,200 @@
+pub mod animate;
+pub mod svg;
+
+use std::io::{self, Write};
+
+use image::{Rgb, RgbImage};
+use png::HasParameters;
+
+use crate::maze::levels::Levels;
+use crate::maze::node::Position;
+use crate::maze::stream::RowSource;
+
+/// Paints `maze_path` onto `buf`, fading from blue at the start to red at the
+/// end. Consecutive positions must share a row or a column, or be diagonal
+/// neighbors.
+pub fn draw_path(buf: &mut RgbImage, maze_path: &[Position]) {
+    draw_path_with_jumps(buf, maze_path, &[]);
+}
+
+/// [`draw_path`] for a path that jumps through portals. The steps starting at
+/// the indices in `jumps`, as in
+/// [`SolveResult::jumps`](crate::pathfinder::SolveResult::jumps), are drawn as
+/// dotted straight lines across the image, and may join any two positions.
+pub fn draw_path_with_jumps(buf: &mut RgbImage, maze_path: &[Position], jumps: &[usize]) {
+    draw_steps(
+        &mut |pos, px| buf[pos.as_tuple()] = px,
+        maze_path,
+        jumps,
+        Some,
+    );
+}
+
+/// The pixels [`draw_path_with_jumps`] paints, in the order it paints them.
+/// Later pixels cover earlier ones at the same position.
+pub fn path_pixels(maze_path: &[Position], jumps: &[usize]) -> Vec<(Position, Rgb<u8>)> {
+    let mut pixels = Vec::new();
+    draw_steps(
+        &mut |pos, px| pixels.push((pos, px)),
+        maze_path,
+        jumps,
+        Some,
+    );
+    pixels
+}
+
+/// Writes the image in `source` to `out` as a PNG file with `pixels`, as
+/// from [`path_pixels`], painted over it. Rows are copied one at a time, so
+/// the image is never held in memory whole.
+pub fn stream_png(
+    source: &mut dyn RowSource,
+    out: impl Write,
+    pixels: &[(Position, Rgb<u8>)],
+) -> io::Result<()> {
+    let (width, height) = source.dimensions();
+    let mut encoder = png::Encoder::new(out, width, height);
+    encoder.set(png::ColorType::RGB).set(png::BitDepth::Eight);
+    let writer = encoder.write_header().map_err(invalid_data)?;
+    let mut zlib = deflate::write::ZlibEncoder::new(
+        IdatWriter {
+            writer,
+            buf: Vec::new(),
+        },
+        deflate::Compression::Default,
+    );
+
+    // stable, so later pixels still cover earlier ones
+    let mut pixels = pixels.to_vec();
+    pixels.sort_by_key(|&(pos, _)| pos.row());
+    let mut pixels = pixels.iter().peekable();
+
+    // each row starts with the filter type, none
+    let mut row = vec![0; width as usize * 3 + 1];
+    for y in 0..height {
+        source.read_row(&mut row[1..]).map_err(invalid_data)?;
+        while let Some((pos, px)) = pixels.next_if(|(pos, _)| pos.row() == y) {
+            let i = pos.col() as usize * 3 + 1;
+            row[i..i + 3].copy_from_slice(&px.data);
+        }
+        zlib.write_all(&row)?;
+    }
+    zlib.finish()?.flush()
+}
+
+/// [`draw_path_with_jumps`] for floor `level` of a maze built from
+/// [`Levels`]. `buf` is that floor's image and only the steps on it are
+/// drawn.
+pub fn draw_level(
+    buf: &mut RgbImage,
+    levels: &Levels,
+    level: usize,
+    maze_path: &[Position],
+    jumps: &[usize],
+) {
+    let mut paint = |pos: Position, px| buf[pos.as_tuple()] = px;
+    draw_steps(&mut paint, maze_path, jumps, |pos| {
+        match levels.level_of(pos) {
+            Some((l, pos)) if l == level => Some(pos),
+            _ => None,
+        }
+    });
+}
+
+/// Paints every step of `maze_path` whose ends `map` places on the image.
+/// Ends of steps leaving the image are still painted.
+fn draw_steps(
+    paint: &mut impl FnMut(Position, Rgb<u8>),
+    maze_path: &[Position],
+    jumps: &[usize],
+    map: impl Fn(Position) -> Option<Position>,
+) {
+    let length = maze_path.len();
+
+    for i in 0..length.saturating_sub(1) {
+        // Blue - red
+        let r = ((i as f32 / length as f32) * 255.0) as u8;
+        let px = Rgb {
+            data: [r, 0, 255 - r],
+        };
+
+        let (a, b) = match (map(maze_path[i]), map(maze_path[i + 1])) {
+            (Some(a), Some(b)) => (a, b),
+            (Some(end), None) | (None, Some(end)) => {
+                paint(end, px);
+                continue;
+            }
+            (None, None) => continue,
+        };
+
+        if jumps.contains(&i) {
+            draw_line(paint, a, b, px, 2);
+        } else if a.row() == b.row() {
+            for x in u32::min(a.col(), b.col())..=u32::max(a.col(), b.col()) {
+                paint(Position::new(x, a.row()), px);
+            }
+        } else if a.col() == b.col() {
+            for y in u32::min(a.row(), b.row())..=u32::max(a.row(), b.row()) {
+                paint(Position::new(a.col(), y), px);
+            }
+        } else {
+            draw_line(paint, a, b, px, 1);
+        }
+    }
+}
+
+/// Paints both ends of the line from `a` to `b` and every `spacing`th pixel
+/// between them. Jumps are dotted with a spacing of 2.
+fn draw_line(
+    paint: &mut impl FnMut(Position, Rgb<u8>),
+    a: Position,
+    b: Position,
+    px: Rgb<u8>,
+    spacing: i64,
+) {
+    let (ax, ay) = (i64::from(a.col()), i64::from(a.row()));
+    let (bx, by) = (i64::from(b.col()), i64::from(b.row()));
+    let steps = i64::max((bx - ax).abs(), (by - ay).abs());
+
+    for s in 0..=steps {
+        if s % spacing != 0 && s != steps {
+            continue;
+        }
+        let (x, y) = if steps == 0 {
+            (ax, ay)
+        } else {
+            (ax + (bx - ax) * s / steps, ay + (by - ay) * s / steps)
+        };
+        paint(Position::new(x as u32, y as u32), px);
+    }
+}
+
+/// Compressed image data written out as `IDAT` chunks of a PNG file. The
+/// file ends when this is dropped.
+struct IdatWriter<W: Write> {
+    writer: png::Writer<W>,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> Write for IdatWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= 1 << 16 {
+            self.flush()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.buf.is_empty() {
+            self.writer
+                .write_chunk(*b"IDAT", &self.buf)
+                .map_err(invalid_data)?;
+            self.buf.clear();
+        }
+        Ok(())
+    }
+}
+
+fn invalid_data(e: impl std::error::Error + Send + Sync + 'static) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
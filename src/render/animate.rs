@@ -0,0 +1,355 @@
+//! Animations of a search spreading through the maze, one frame for every
+//! few nodes it expands.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use gif::SetParameter;
+use image::{Rgb, RgbImage};
+
+use crate::maze::node::{NodeIndex, Position};
+use crate::maze::Maze;
+use crate::pathfinder::SearchEvent;
+
+/// Nodes waiting on the frontier.
+const FRONTIER: Rgb<u8> = Rgb {
+    data: [255, 160, 0],
+};
+/// Nodes expanded in the current frame, and the corridors leaving them.
+const NEW: Rgb<u8> = Rgb { data: [0, 200, 0] };
+/// Nodes expanded in earlier frames.
+const EXPANDED: Rgb<u8> = Rgb {
+    data: [160, 230, 160],
+};
+
+/// How [`write_gif`] animates a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationOptions {
+    /// Nodes expanded between two frames.
+    pub stride: usize,
+    /// Width and height of a maze pixel in the animation.
+    pub scale: u32,
+    /// Time each frame is shown, in hundredths of a second. The frame with
+    /// the path stays up longer.
+    pub delay: u16,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> AnimationOptions {
+        AnimationOptions {
+            stride: 1,
+            scale: 1,
+            delay: 4,
+        }
+    }
+}
+
+/// Writes an animated GIF file of the search that sent `events` to a
+/// [`Recorder`](crate::pathfinder::Recorder) to `out`. `img` is the image
+/// `maze` was built from. Every frame shows the nodes expanded since the
+/// last one and the current frontier, and the last frame adds `maze_path`,
+/// drawn as by [`draw_path_with_jumps`](super::draw_path_with_jumps).
+pub fn write_gif(
+    out: impl Write,
+    img: &RgbImage,
+    maze: &Maze,
+    events: &[SearchEvent],
+    maze_path: &[Position],
+    jumps: &[usize],
+    options: &AnimationOptions,
+) -> io::Result<()> {
+    let scale = options.scale.max(1);
+    let stride = options.stride.max(1);
+    let (width, height) = img.dimensions();
+    let size = |n: u32| {
+        let n = u64::from(n) * u64::from(scale);
+        if n > u64::from(u16::MAX) {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the animation is too large for a gif file",
+            ))
+        } else {
+            Ok(n as u16)
+        }
+    };
+    let path = super::path_pixels(maze_path, jumps);
+
+    let mut palette = Palette::new(img, path.iter().map(|&(_, px)| px));
+    let mut encoder = gif::Encoder::new(out, size(width)?, size(height)?, &palette.flat())?;
+    encoder.set(gif::Repeat::Infinite)?;
+    let mut canvas = Canvas {
+        img: img.clone(),
+        dirty: None,
+    };
+    // the first frame is the whole maze
+    canvas.paint(Position::new(0, 0), img[(0, 0)]);
+    canvas.paint(
+        Position::new(width - 1, height - 1),
+        img[(width - 1, height - 1)],
+    );
+
+    let mut new = Vec::new();
+    let mut frontier = HashSet::new();
+    let mut popped = 0;
+    for &event in events {
+        match event {
+            SearchEvent::Push(node) => {
+                frontier.insert(node);
+                canvas.paint(maze.node(node).position(), FRONTIER);
+            }
+            SearchEvent::Pop(node) => {
+                frontier.remove(&node);
+                paint_expansion(&mut canvas, maze, node, NEW);
+                new.push(node);
+                popped += 1;
+                if popped % stride == 0 {
+                    canvas.write_frame(&mut encoder, &mut palette, scale, options.delay)?;
+                    for node in new.drain(..) {
+                        paint_expansion(&mut canvas, maze, node, EXPANDED);
+                        // pushed again since
+                        if frontier.contains(&node) {
+                            canvas.paint(maze.node(node).position(), FRONTIER);
+                        }
+                    }
+                }
+            }
+            SearchEvent::Relax { .. } => {}
+        }
+    }
+    for (pos, px) in path {
+        canvas.paint(pos, px);
+    }
+    canvas.write_frame(
+        &mut encoder,
+        &mut palette,
+        scale,
+        options.delay.saturating_mul(50),
+    )
+}
+
+/// Paints `node` and the corridors to its neighbors, but not the neighbors
+/// themselves, in `px`. Jumps through portals are left out.
+fn paint_expansion(canvas: &mut Canvas, maze: &Maze, node: NodeIndex, px: Rgb<u8>) {
+    let a = maze.node(node).position();
+    canvas.paint(a, px);
+    for (n, _) in maze.node(node).edges() {
+        if maze.node(node).jumps_to(n) {
+            continue;
+        }
+        let b = maze.node(n).position();
+        super::draw_line(
+            &mut |pos, px| {
+                if pos != b {
+                    canvas.paint(pos, px)
+                }
+            },
+            a,
+            b,
+            px,
+            1,
+        );
+    }
+}
+
+/// The image being animated, with the area painted since the last frame.
+struct Canvas {
+    img: RgbImage,
+    /// First and last column and row painted.
+    dirty: Option<(u32, u32, u32, u32)>,
+}
+
+impl Canvas {
+    fn paint(&mut self, pos: Position, px: Rgb<u8>) {
+        let (x, y) = pos.as_tuple();
+        self.img[(x, y)] = px;
+        self.dirty = Some(match self.dirty {
+            Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            None => (x, y, x, y),
+        });
+    }
+
+    /// Writes the painted area as the next frame, `scale` times as large.
+    fn write_frame(
+        &mut self,
+        encoder: &mut gif::Encoder<impl Write>,
+        palette: &mut Palette,
+        scale: u32,
+        delay: u16,
+    ) -> io::Result<()> {
+        let (x0, y0, x1, y1) = match self.dirty.take() {
+            Some(area) => area,
+            None => return Ok(()),
+        };
+        let (width, height) = ((x1 - x0 + 1) * scale, (y1 - y0 + 1) * scale);
+        let mut buffer = Vec::with_capacity(width as usize * height as usize);
+        for y in y0..=y1 {
+            let start = buffer.len();
+            for x in x0..=x1 {
+                let index = palette.index(self.img[(x, y)]);
+                buffer.extend((0..scale).map(|_| index));
+            }
+            for _ in 1..scale {
+                buffer.extend_from_within(start..start + width as usize);
+            }
+        }
+        encoder.write_frame(&gif::Frame {
+            delay,
+            left: (x0 * scale) as u16,
+            top: (y0 * scale) as u16,
+            width: width as u16,
+            height: height as u16,
+            buffer: Cow::Owned(buffer),
+            ..gif::Frame::default()
+        })
+    }
+}
+
+/// The 256 colors a GIF file may use. Colors without an entry of their own
+/// get the closest one.
+struct Palette {
+    colors: Vec<Rgb<u8>>,
+    index: HashMap<Rgb<u8>, u8>,
+}
+
+impl Palette {
+    /// A palette for `img` with the search colors and `path` added. If there
+    /// are too many colors, the image gets a color cube instead of its own.
+    fn new(img: &RgbImage, path: impl Iterator<Item = Rgb<u8>>) -> Palette {
+        let mut colors = vec![FRONTIER, NEW, EXPANDED];
+        let mut seen: HashSet<Rgb<u8>> = colors.iter().copied().collect();
+        for px in path {
+            if seen.insert(px) {
+                colors.push(px);
+            }
+        }
+        let path_end = colors.len();
+        for &px in img.pixels() {
+            if seen.insert(px) {
+                colors.push(px);
+            }
+        }
+        if colors.len() > 256 {
+            let path = colors[3..path_end].to_vec();
+            colors.truncate(3);
+            for r in 0..6 {
+                for g in 0..6 {
+                    for b in 0..6 {
+                        colors.push(Rgb {
+                            data: [r * 51, g * 51, b * 51],
+                        });
+                    }
+                }
+            }
+            // an even sample of the path colors fills the rest
+            let room = 256 - colors.len();
+            let step = path.len().div_ceil(room);
+            colors.extend(path.into_iter().step_by(step.max(1)));
+        }
+        let index = colors
+            .iter()
+            .enumerate()
+            .map(|(i, &px)| (px, i as u8))
+            .collect();
+        Palette { colors, index }
+    }
+
+    fn flat(&self) -> Vec<u8> {
+        self.colors.iter().flat_map(|px| px.data).collect()
+    }
+
+    fn index(&mut self, px: Rgb<u8>) -> u8 {
+        let colors = &self.colors;
+        *self.index.entry(px).or_insert_with(|| {
+            let distance = |c: &Rgb<u8>| -> u32 {
+                (0..3)
+                    .map(|i| {
+                        let d = i32::from(c[i]) - i32::from(px[i]);
+                        (d * d) as u32
+                    })
+                    .sum()
+            };
+            (0..colors.len())
+                .min_by_key(|&i| distance(&colors[i]))
+                .unwrap_or(0) as u8
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinder::{self, Recorder};
+    use crate::render;
+
+    /// The frames of a GIF file drawn over each other, as colors of the
+    /// global palette, with the number of frames.
+    fn play(gif: &[u8]) -> (RgbImage, usize) {
+        let mut reader = gif::Decoder::new(gif).read_info().unwrap();
+        let (width, height) = (u32::from(reader.width()), u32::from(reader.height()));
+        let palette = reader.global_palette().unwrap().to_vec();
+        let mut screen = RgbImage::new(width, height);
+        let mut frames = 0;
+        while let Some(frame) = reader.read_next_frame().unwrap() {
+            frames += 1;
+            let (left, top) = (u32::from(frame.left), u32::from(frame.top));
+            for (i, &index) in frame.buffer.iter().enumerate() {
+                let (x, y) = (
+                    i as u32 % u32::from(frame.width),
+                    i as u32 / u32::from(frame.width),
+                );
+                let color = &palette[usize::from(index) * 3..usize::from(index) * 3 + 3];
+                screen[(left + x, top + y)] = Rgb {
+                    data: [color[0], color[1], color[2]],
+                };
+            }
+        }
+        (screen, frames)
+    }
+
+    #[test]
+    fn frames_and_final_path() {
+        let img = image::load_from_memory(include_bytes!("../../img/tiny.png"))
+            .unwrap()
+            .to_rgb();
+        let maze = Maze::new(&img).unwrap();
+        let mut recorder = Recorder::new();
+        let result = pathfinder::bfs_observed(&maze, &mut recorder);
+        let path = result.path.unwrap();
+
+        for &(stride, scale) in &[(1, 1), (3, 2)] {
+            let mut out = Vec::new();
+            let options = AnimationOptions {
+                stride,
+                scale,
+                ..AnimationOptions::default()
+            };
+            write_gif(
+                &mut out,
+                &img,
+                &maze,
+                recorder.events(),
+                &path,
+                &result.jumps,
+                &options,
+            )
+            .unwrap();
+            let (screen, frames) = play(&out);
+            assert_eq!(
+                screen.dimensions(),
+                (img.width() * scale, img.height() * scale)
+            );
+            // a frame every `stride` expansions, then the one with the path
+            assert_eq!(frames, result.expanded / stride + 1);
+            let at = |pos: Position| screen[(pos.col() * scale, pos.row() * scale)];
+            // later steps paint over earlier ones at the corners
+            let painted: HashMap<_, _> = render::path_pixels(&path, &result.jumps)
+                .into_iter()
+                .collect();
+            for (&pos, &px) in &painted {
+                assert_eq!(at(pos), px);
+            }
+            assert_eq!(at(Position::new(0, 0)), img[(0, 0)]);
+        }
+    }
+}
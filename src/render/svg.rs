@@ -0,0 +1,333 @@
+//! Scalable drawings of a maze and its solution, for mazes too small or too
+//! large to show well as images.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::io::{self, Write};
+
+use image::{Rgb, RgbImage};
+
+use crate::maze::classify::WallClassifier;
+use crate::maze::node::Position;
+
+/// How [`write_svg`] draws the solution.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SvgOptions {
+    /// Width of the path line, in pixels of the maze image.
+    pub stroke_width: f32,
+    /// Marks every node the search reached with a dot.
+    pub show_visited: bool,
+}
+
+impl Default for SvgOptions {
+    fn default() -> SvgOptions {
+        SvgOptions {
+            stroke_width: 1.0,
+            show_visited: false,
+        }
+    }
+}
+
+/// Writes an SVG drawing of the maze in `img` to `out`, with the walls as
+/// rectangles on the most common open color and `maze_path` as one line
+/// shaded from blue at the start to red at the exit, with dashed jumps like
+/// [`draw_path_with_jumps`](super::draw_path_with_jumps). `visited` is drawn
+/// only if `options` asks for it.
+pub fn write_svg(
+    mut out: impl Write,
+    img: &RgbImage,
+    walls: &WallClassifier,
+    maze_path: &[Position],
+    jumps: &[usize],
+    visited: &HashSet<Position>,
+    options: &SvgOptions,
+) -> io::Result<()> {
+    let (width, height) = img.dimensions();
+    writeln!(
+        out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width,
+        h = height
+    )?;
+    writeln!(
+        out,
+        r#"<rect width="{}" height="{}" fill="{}"/>"#,
+        width,
+        height,
+        hex(open_color(img, walls))
+    )?;
+
+    writeln!(
+        out,
+        r#"<g fill="{}" shape-rendering="crispEdges">"#,
+        hex(walls.wall_color())
+    )?;
+    for (x, y, w, h) in wall_rects(img, walls) {
+        writeln!(
+            out,
+            r#"<rect x="{}" y="{}" width="{}" height="{}"/>"#,
+            x, y, w, h
+        )?;
+    }
+    writeln!(out, "</g>")?;
+
+    if options.show_visited && !visited.is_empty() {
+        // sorted so the same search always writes the same file
+        let mut visited: Vec<_> = visited.iter().collect();
+        visited.sort_by_key(|pos| (pos.row(), pos.col()));
+        writeln!(out, r##"<g fill="#00c000" fill-opacity="0.5">"##)?;
+        for pos in visited {
+            let (x, y) = center(*pos);
+            writeln!(
+                out,
+                r#"<circle cx="{}" cy="{}" r="{}"/>"#,
+                x, y, options.stroke_width
+            )?;
+        }
+        writeln!(out, "</g>")?;
+    }
+
+    if maze_path.len() > 1 {
+        writeln!(
+            out,
+            r#"<g fill="none" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round">"#,
+            options.stroke_width
+        )?;
+        write_path(&mut out, maze_path, jumps, options.stroke_width)?;
+        writeln!(out, "</g>")?;
+    }
+    writeln!(out, "</svg>")
+}
+
+/// Writes the steps of `maze_path` as a single path, leaving gaps for the
+/// jumps, which are dashed lines of their own. Both are stroked with a
+/// gradient from blue at the start to red at the exit, as in `draw_steps`.
+fn write_path(
+    out: &mut impl Write,
+    maze_path: &[Position],
+    jumps: &[usize],
+    stroke_width: f32,
+) -> io::Result<()> {
+    let (x1, y1) = center(maze_path[0]);
+    let (x2, y2) = center(maze_path[maze_path.len() - 1]);
+    writeln!(
+        out,
+        r#"<defs><linearGradient id="path" gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}">"#,
+        x1, y1, x2, y2
+    )?;
+    writeln!(
+        out,
+        r##"<stop offset="0" stop-color="#0000ff"/><stop offset="1" stop-color="#ff0000"/>"##
+    )?;
+    writeln!(out, "</linearGradient></defs>")?;
+
+    let mut d = String::new();
+    for (i, w) in maze_path.windows(2).enumerate() {
+        let (ax, ay) = center(w[0]);
+        let (bx, by) = center(w[1]);
+        if jumps.contains(&i) {
+            writeln!(
+                out,
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="url(#path)" stroke-dasharray="{}"/>"#,
+                ax,
+                ay,
+                bx,
+                by,
+                stroke_width * 2.0
+            )?;
+            continue;
+        }
+        // a new subpath after the start and after every jump
+        if i == 0 || jumps.contains(&(i - 1)) {
+            d += &format!("M{},{}", ax, ay);
+        }
+        d += &format!("L{},{}", bx, by);
+    }
+    if !d.is_empty() {
+        writeln!(out, r#"<path d="{}" stroke="url(#path)"/>"#, d)?;
+    }
+    Ok(())
+}
+
+/// The color most open pixels of `img` have, or the opposite of the walls in
+/// a maze without any.
+fn open_color(img: &RgbImage, walls: &WallClassifier) -> Rgb<u8> {
+    let mut counts: HashMap<[u8; 3], usize> = HashMap::new();
+    for px in img.pixels() {
+        if !walls.is_wall(*px) {
+            *counts.entry(px.data).or_insert(0) += 1;
+        }
+    }
+    counts
+        .into_iter()
+        .max_by_key(|&(color, n)| (n, Reverse(color)))
+        .map_or_else(|| walls.inverted().wall_color(), |(data, _)| Rgb { data })
+}
+
+/// Covers the walls of `img` with rectangles, as `(x, y, width, height)`.
+/// Runs of wall pixels in a row are merged with the same runs in the rows
+/// below.
+fn wall_rects(img: &RgbImage, walls: &WallClassifier) -> Vec<(u32, u32, u32, u32)> {
+    let (width, height) = img.dimensions();
+    let mut rects = Vec::new();
+    // runs still growing downwards, as their first and last column plus one,
+    // and their first row
+    let mut open: Vec<(u32, u32, u32)> = Vec::new();
+    let mut close = |(x0, x1, y0): (u32, u32, u32), y: u32| rects.push((x0, y0, x1 - x0, y - y0));
+
+    for y in 0..=height {
+        let mut runs = Vec::new();
+        let mut x = 0;
+        while y < height && x < width {
+            if walls.is_wall(*img.get_pixel(x, y)) {
+                let start = x;
+                while x < width && walls.is_wall(*img.get_pixel(x, y)) {
+                    x += 1;
+                }
+                runs.push((start, x));
+            } else {
+                x += 1;
+            }
+        }
+
+        // both lists are in column order
+        let mut next = Vec::with_capacity(runs.len());
+        let mut i = 0;
+        for (x0, x1) in runs {
+            while i < open.len() && open[i].0 < x0 {
+                close(open[i], y);
+                i += 1;
+            }
+            match open.get(i) {
+                Some(&run) if run.0 == x0 && run.1 == x1 => {
+                    next.push(run);
+                    i += 1;
+                    continue;
+                }
+                Some(&run) if run.0 == x0 => {
+                    close(run, y);
+                    i += 1;
+                }
+                _ => {}
+            }
+            next.push((x0, x1, y));
+        }
+        for &run in &open[i..] {
+            close(run, y);
+        }
+        open = next;
+    }
+    rects
+}
+
+/// The middle of the pixel at `pos`.
+fn center(pos: Position) -> (f32, f32) {
+    (pos.col() as f32 + 0.5, pos.row() as f32 + 0.5)
+}
+
+fn hex(px: Rgb<u8>) -> String {
+    let [r, g, b] = px.data;
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An image drawn as text, `#` in `wall` and everything else in `open`.
+    fn draw(rows: &[&str], wall: Rgb<u8>, open: Rgb<u8>) -> RgbImage {
+        RgbImage::from_fn(rows[0].len() as u32, rows.len() as u32, |x, y| {
+            match rows[y as usize].as_bytes()[x as usize] {
+                b'#' => wall,
+                _ => open,
+            }
+        })
+    }
+
+    fn gray(v: u8) -> Rgb<u8> {
+        Rgb { data: [v, v, v] }
+    }
+
+    fn svg(img: &RgbImage, walls: &WallClassifier, path: &[Position], jumps: &[usize]) -> String {
+        let mut out = Vec::new();
+        let options = SvgOptions::default();
+        write_svg(&mut out, img, walls, path, jumps, &HashSet::new(), &options).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn runs_merge_down_the_rows() {
+        let walls = WallClassifier::default();
+        let ring = draw(&["###", "#.#", "###"], gray(0), gray(255));
+        assert_eq!(
+            wall_rects(&ring, &walls),
+            vec![(0, 0, 3, 1), (0, 1, 1, 1), (2, 1, 1, 1), (0, 2, 3, 1)]
+        );
+        let columns = draw(&["#.#", "#.#", "#.#"], gray(0), gray(255));
+        assert_eq!(
+            wall_rects(&columns, &walls),
+            vec![(0, 0, 1, 3), (2, 0, 1, 3)]
+        );
+    }
+
+    #[test]
+    fn rects_cover_exactly_the_walls() {
+        let img = image::load_from_memory(include_bytes!("../../img/normal.png"))
+            .unwrap()
+            .to_rgb();
+        let walls = WallClassifier::default();
+        let mut covered = vec![0; (img.width() * img.height()) as usize];
+        for (x, y, w, h) in wall_rects(&img, &walls) {
+            for py in y..y + h {
+                for px in x..x + w {
+                    covered[(py * img.width() + px) as usize] += 1;
+                }
+            }
+        }
+        for (x, y, px) in img.enumerate_pixels() {
+            let expected = if walls.is_wall(*px) { 1 } else { 0 };
+            assert_eq!(covered[(y * img.width() + x) as usize], expected);
+        }
+    }
+
+    #[test]
+    fn background_is_the_open_color() {
+        let rows = ["#.#", "#.#", "#.#"];
+        let walls = WallClassifier::Color {
+            color: gray(128),
+            tolerance: 0,
+        };
+        let drawing = svg(&draw(&rows, gray(128), gray(255)), &walls, &[], &[]);
+        assert!(drawing.contains(r##"<rect width="3" height="3" fill="#ffffff"/>"##));
+        // light walls on a dark floor
+        let drawing = svg(&draw(&rows, gray(128), gray(20)), &walls, &[], &[]);
+        assert!(drawing.contains(r##"<rect width="3" height="3" fill="#141414"/>"##));
+    }
+
+    #[test]
+    fn one_gradient_path_with_dashed_jumps() {
+        let img = draw(&["#.###", "#...#", "###.#"], gray(0), gray(255));
+        let path = [
+            Position::new(1, 0),
+            Position::new(1, 1),
+            Position::new(3, 1),
+            Position::new(3, 2),
+        ];
+        let drawing = svg(&img, &WallClassifier::default(), &path, &[]);
+        assert_eq!(drawing.matches("<path ").count(), 1);
+        assert!(drawing.contains(r#"x1="1.5" y1="0.5" x2="3.5" y2="2.5""#));
+        assert!(
+            drawing.contains(r#"<path d="M1.5,0.5L1.5,1.5L3.5,1.5L3.5,2.5" stroke="url(#path)"/>"#)
+        );
+        assert!(!drawing.contains("<line "));
+
+        // the jump from the corner splits the path in two
+        let drawing = svg(&img, &WallClassifier::default(), &path, &[1]);
+        assert!(
+            drawing.contains(r#"<path d="M1.5,0.5L1.5,1.5M3.5,1.5L3.5,2.5" stroke="url(#path)"/>"#)
+        );
+        assert!(drawing.contains(
+            r#"<line x1="1.5" y1="1.5" x2="3.5" y2="1.5" stroke="url(#path)" stroke-dasharray="2"/>"#
+        ));
+    }
+}